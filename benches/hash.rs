@@ -0,0 +1,58 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasher,
+    thread,
+};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use quid::{UidBuildHasher, UID};
+
+const THREADS: usize = 4;
+
+// IDs as a real program sees them: interleaved blocks from several threads
+fn ids_from_threads(n: usize) -> Vec<UID> {
+    let handles: Vec<_> =
+        (0..THREADS).map(|_| thread::spawn(move || (0..n / THREADS).map(|_| UID::new()).collect::<Vec<_>>())).collect();
+    handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+}
+
+fn insert<S: BuildHasher + Default>(ids: &[UID]) -> HashMap<UID, usize, S> {
+    let mut map = HashMap::with_hasher(S::default());
+    for (i, &uid) in ids.iter().enumerate() {
+        map.insert(uid, i);
+    }
+    map
+}
+
+fn bench_maps(c: &mut Criterion) {
+    for n in [1_000, 100_000] {
+        let ids = ids_from_threads(n);
+
+        let mut group = c.benchmark_group("insert");
+        group.bench_function(BenchmarkId::new("siphash", n), |b| {
+            b.iter(|| insert::<std::hash::RandomState>(black_box(&ids)))
+        });
+        group.bench_function(BenchmarkId::new("uid", n), |b| b.iter(|| insert::<UidBuildHasher>(black_box(&ids))));
+        group.finish();
+
+        let default = insert::<std::hash::RandomState>(&ids);
+        let fast = insert::<UidBuildHasher>(&ids);
+        let mut group = c.benchmark_group("lookup");
+        group.bench_function(BenchmarkId::new("siphash", n), |b| {
+            b.iter(|| ids.iter().filter(|uid| default.contains_key(black_box(uid))).count())
+        });
+        group.bench_function(BenchmarkId::new("uid", n), |b| {
+            b.iter(|| ids.iter().filter(|uid| fast.contains_key(black_box(uid))).count())
+        });
+        group.finish();
+    }
+
+    let ids = ids_from_threads(100_000);
+    let mut group = c.benchmark_group("set");
+    group.bench_function("siphash", |b| b.iter(|| ids.iter().copied().collect::<HashSet<_>>().len()));
+    group.bench_function("uid", |b| b.iter(|| ids.iter().copied().collect::<quid::UidSet>().len()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_maps);
+criterion_main!(benches);
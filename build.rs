@@ -0,0 +1,29 @@
+use std::env;
+
+// emits `quid_uid32` when `UidTy` is a `u32`, only with QUID_UID_BITS=32. not a feature,
+// since one crate turning it on would change `UidTy` for every other user of quid in the build.
+// emits `quid_native_atomic` when the target has atomics as wide as `UidTy`.
+// set QUID_FORCE_FALLBACK to build (and test) the fallback counter on any target
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(quid_native_atomic)");
+    println!("cargo::rustc-check-cfg=cfg(quid_uid32)");
+    println!("cargo::rerun-if-env-changed=QUID_FORCE_FALLBACK");
+    println!("cargo::rerun-if-env-changed=QUID_UID_BITS");
+
+    let atomics = env::var("CARGO_CFG_TARGET_HAS_ATOMIC").unwrap_or_default();
+    let has_atomic = |width: &str| atomics.split(',').any(|w| w == width);
+
+    let uid32 = match env::var("QUID_UID_BITS").as_deref() {
+        Ok("32") => true,
+        Ok("64") => false,
+        Ok(bits) => panic!("QUID_UID_BITS must be 32 or 64, not {:?}", bits),
+        Err(_) => false,
+    };
+    if uid32 {
+        println!("cargo::rustc-cfg=quid_uid32");
+    }
+
+    if has_atomic(if uid32 { "32" } else { "64" }) && env::var_os("QUID_FORCE_FALLBACK").is_none() {
+        println!("cargo::rustc-cfg=quid_native_atomic");
+    }
+}
@@ -0,0 +1,56 @@
+use std::{env, net::TcpListener, process};
+
+use quid::{net, HighWaterMark, UidGenerator, UidTy};
+
+static GEN: UidGenerator = UidGenerator::new();
+
+const USAGE: &str = "usage: quid-server [ADDR] [--state FILE] [--chunk N]
+
+Leases blocks of IDs over TCP, on 127.0.0.1:7420 by default.
+With --state, the high-water mark is kept in FILE so a restarted server never
+hands out an ID twice; --chunk sets how far ahead it is written.";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("quid-server: {}", msg);
+    process::exit(2)
+}
+
+fn main() {
+    let mut addr = format!("127.0.0.1:{}", net::DEFAULT_PORT);
+    let mut state = None;
+    let mut chunk = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--state" => state = Some(args.next().unwrap_or_else(|| fail("--state needs a file"))),
+            "--chunk" => {
+                let n = args.next().and_then(|n| n.parse::<UidTy>().ok()).filter(|&n| n > 0);
+                chunk = Some(n.unwrap_or_else(|| fail("--chunk needs a positive number")));
+            }
+            _ if arg.starts_with('-') => fail(&format!("unknown option {}\n\n{}", arg, USAGE)),
+            _ => addr = arg,
+        }
+    }
+
+    if let Some(path) = state {
+        let mut mark = HighWaterMark::new(path);
+        if let Some(chunk) = chunk {
+            mark = mark.with_chunk(chunk);
+        }
+        if let Err(err) = GEN.persist(mark) {
+            fail(&format!("can't use the state file: {}", err));
+        }
+    }
+
+    let listener = TcpListener::bind(&addr).unwrap_or_else(|err| fail(&format!("can't listen on {}: {}", addr, err)));
+    eprintln!("quid-server: listening on {}", addr);
+    let res = net::serve(listener, &GEN, |err| eprintln!("quid-server: accepting a connection failed: {}", err));
+    if let Err(err) = res {
+        fail(&err.to_string());
+    }
+}
@@ -0,0 +1,339 @@
+use std::{env, process};
+
+#[cfg(not(quid_uid32))]
+use quid::{Layout, SnowflakeGenerator, DEFAULT_EPOCH};
+use quid::{Encoding, HighWaterMark, Ulid, Uuid, UuidV8Generator, UID};
+
+const USAGE: &str = "usage:
+  quid gen [-n COUNT] [--kind KIND] [--format FORMAT] [--check] [--node N] [--state FILE]
+  quid decode [--from FORMAT] [--kind KIND] [--epoch MS] [--layout T,N,S] ID...
+  quid check ID...
+
+gen prints COUNT new IDs (1 by default), one per line.
+  KIND is uid (the default), snowflake, ulid, uuid-v7 or uuid-v8.
+  FORMAT is decimal (the default), hex, base32 or base62, for uid and snowflake.
+  --check appends the check symbol to base32.
+  uid IDs start from 1 again in every run unless --state keeps a high-water mark in FILE.
+
+decode prints every form of each ID, and its parts for snowflakes, ULIDs and UUIDs.
+  IDs are read as decimal or 0x-prefixed hex unless --from says otherwise.
+  KIND is uid, snowflake, ulid or uuid; by default 26 characters are a ULID,
+  32 or 36 a UUID, and anything else a uid.
+  --epoch and --layout describe the snowflakes, the defaults match SnowflakeGenerator.
+
+check validates base32 IDs ending in a check symbol, and exits with 1 if any don't.";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("quid: {}", msg);
+    process::exit(2)
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let cmd = args.next().unwrap_or_else(|| fail(USAGE));
+    let args: Vec<String> = args.collect();
+    let ok = match cmd.as_str() {
+        "gen" => gen(&args),
+        "decode" => decode(&args),
+        "check" => check(&args),
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            true
+        }
+        _ => fail(&format!("unknown command {}\n\n{}", cmd, USAGE)),
+    };
+    if !ok {
+        process::exit(1);
+    }
+}
+
+// splits `args` into options, taking a value for the ones in `with_value`, and the rest
+fn parse_opts<'a>(args: &'a [String], with_value: &[&str], flags: &[&str]) -> (Vec<(&'a str, &'a str)>, Vec<&'a str>) {
+    let mut opts = Vec::new();
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            process::exit(0);
+        } else if with_value.contains(&arg.as_str()) {
+            let value = iter.next().unwrap_or_else(|| fail(&format!("{} needs a value", arg)));
+            opts.push((arg.as_str(), value.as_str()));
+        } else if flags.contains(&arg.as_str()) {
+            opts.push((arg.as_str(), ""));
+        } else if arg.starts_with('-') && arg.len() > 1 {
+            fail(&format!("unknown option {}\n\n{}", arg, USAGE));
+        } else {
+            rest.push(arg.as_str());
+        }
+    }
+    (opts, rest)
+}
+
+fn parse_num<T: std::str::FromStr>(opt: &str, value: &str) -> T {
+    value.parse().unwrap_or_else(|_| fail(&format!("invalid {} {}", opt, value)))
+}
+
+fn parse_encoding(value: &str) -> Encoding {
+    match value {
+        "decimal" => Encoding::Decimal,
+        "hex" => Encoding::Hex,
+        "base32" => Encoding::Base32,
+        "base62" => Encoding::Base62,
+        _ => fail(&format!("unknown format {}", value)),
+    }
+}
+
+fn format_uid(uid: &UID, encoding: Encoding, check: bool) -> String {
+    match encoding {
+        Encoding::Decimal => uid.to_string(),
+        Encoding::Hex => format!("{:#x}", uid),
+        Encoding::Base32 if check => uid.base32().with_check().to_string(),
+        Encoding::Base32 => uid.base32().to_string(),
+        Encoding::Base62 => uid.base62().to_string(),
+    }
+}
+
+fn gen(args: &[String]) -> bool {
+    let (opts, rest) = parse_opts(args, &["-n", "--kind", "--format", "--node", "--state"], &["--check"]);
+    if let Some(arg) = rest.first() {
+        fail(&format!("unexpected argument {}", arg));
+    }
+    let mut count: u64 = 1;
+    let mut kind = "uid";
+    let mut encoding = None;
+    let mut check = false;
+    let mut node = None;
+    let mut state = None;
+    for (opt, value) in opts {
+        match opt {
+            "-n" => count = parse_num(opt, value),
+            "--kind" => kind = value,
+            "--format" => encoding = Some(parse_encoding(value)),
+            "--check" => check = true,
+            "--node" => node = Some(parse_num::<u64>(opt, value)),
+            _ => state = Some(value),
+        }
+    }
+    if check && encoding != Some(Encoding::Base32) {
+        fail("--check only goes with --format base32");
+    }
+    if encoding.is_some() && !matches!(kind, "uid" | "snowflake") {
+        fail(&format!("{} IDs have a fixed format", kind));
+    }
+    if node.is_some() && kind != "snowflake" {
+        fail("--node only goes with --kind snowflake");
+    }
+    if state.is_some() && kind != "uid" {
+        fail("--state only goes with --kind uid");
+    }
+    let encoding = encoding.unwrap_or(Encoding::Decimal);
+
+    let mut next: Box<dyn FnMut() -> String> = match kind {
+        "uid" => {
+            if let Some(path) = state {
+                if let Err(err) = UID::persist(HighWaterMark::new(path)) {
+                    fail(&format!("can't use the state file: {}", err));
+                }
+            }
+            Box::new(move || match UID::try_new() {
+                Ok(uid) => format_uid(&uid, encoding, check),
+                Err(err) => fail(&err.to_string()),
+            })
+        }
+        #[cfg(not(quid_uid32))]
+        "snowflake" => {
+            let node = node.unwrap_or(0);
+            if node >> Layout::DEFAULT.node_bits != 0 {
+                fail(&format!("node {} doesn't fit into {} bits", node, Layout::DEFAULT.node_bits));
+            }
+            let gen: &'static SnowflakeGenerator = Box::leak(Box::new(SnowflakeGenerator::new(node)));
+            Box::new(move || match gen.try_next_uid() {
+                Ok(uid) => format_uid(&uid, encoding, check),
+                Err(err) => fail(&err.to_string()),
+            })
+        }
+        "ulid" => Box::new(|| match Ulid::try_new() {
+            Ok(ulid) => ulid.to_string(),
+            Err(err) => fail(&err.to_string()),
+        }),
+        "uuid-v7" => Box::new(|| Uuid::now_v7().to_string()),
+        "uuid-v8" => {
+            let gen = UuidV8Generator::new();
+            Box::new(move || gen.next_uuid().to_string())
+        }
+        _ => fail(&format!("unknown kind {}", kind)),
+    };
+    for _ in 0..count {
+        println!("{}", next());
+    }
+    true
+}
+
+fn decode(args: &[String]) -> bool {
+    let (opts, ids) = parse_opts(args, &["--from", "--kind", "--epoch", "--layout"], &[]);
+    if ids.is_empty() {
+        fail("decode needs at least one ID");
+    }
+    let mut from = None;
+    let mut kind = None;
+    let mut epoch = None;
+    let mut layout = None;
+    for (opt, value) in opts {
+        match opt {
+            "--from" => from = Some(parse_encoding(value)),
+            "--kind" => kind = Some(value),
+            "--epoch" => epoch = Some(parse_num::<u64>(opt, value)),
+            _ => layout = Some(value),
+        }
+    }
+    #[cfg(not(quid_uid32))]
+    let snowflakes = {
+        let mut gen = SnowflakeGenerator::new(0).with_epoch(epoch.unwrap_or(DEFAULT_EPOCH));
+        if let Some(layout) = layout {
+            gen = gen.with_layout(parse_layout(layout));
+        }
+        gen
+    };
+    #[cfg(quid_uid32)]
+    let _ = (epoch, layout);
+
+    let mut ok = true;
+    for (i, id) in ids.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let kind = kind.unwrap_or(match id.len() {
+            26 => "ulid",
+            32 | 36 => "uuid",
+            _ => "uid",
+        });
+        let res = match kind {
+            "uid" => decode_uid(id, from).map(|uid| print_uid(&uid)),
+            #[cfg(not(quid_uid32))]
+            "snowflake" => decode_uid(id, from).map(|uid| {
+                print_uid(&uid);
+                let parts = snowflakes.decode(&uid);
+                println!("timestamp {} ({})", parts.timestamp, utc(parts.timestamp));
+                println!("node      {}", parts.node);
+                println!("sequence  {}", parts.sequence);
+            }),
+            "ulid" => id.parse::<Ulid>().map(|ulid| {
+                println!("ulid      {}", ulid);
+                println!("hex       {:#034x}", ulid.to_u128());
+                println!("timestamp {} ({})", ulid.timestamp(), utc(ulid.timestamp()));
+                println!("random    {:#022x}", ulid.random());
+            }),
+            "uuid" => id.parse::<Uuid>().map(|uuid| {
+                println!("uuid      {}", uuid);
+                println!("version   {}", uuid.version());
+                if matches!(uuid.version(), 7 | 8) {
+                    println!("timestamp {} ({})", uuid.timestamp(), utc(uuid.timestamp()));
+                }
+            }),
+            _ => fail(&format!("unknown kind {}", kind)),
+        };
+        if let Err(err) = res {
+            eprintln!("quid: {}: {}", id, err);
+            ok = false;
+        }
+    }
+    ok
+}
+
+fn check(args: &[String]) -> bool {
+    let (_, ids) = parse_opts(args, &[], &[]);
+    if ids.is_empty() {
+        fail("check needs at least one ID");
+    }
+    let mut ok = true;
+    for id in ids {
+        match UID::from_base32_checked(id) {
+            Ok(uid) => println!("{}: ok ({})", id, uid),
+            Err(err) => {
+                println!("{}: {}", id, err);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+fn decode_uid(id: &str, from: Option<Encoding>) -> Result<UID, quid::ParseUidError> {
+    match from {
+        Some(encoding) => UID::parse_as(id, encoding),
+        None => id.parse(),
+    }
+}
+
+fn print_uid(uid: &UID) {
+    println!("decimal   {}", uid);
+    println!("hex       {:#x}", uid);
+    println!("base32    {} (checked {})", uid.base32(), uid.base32().with_check());
+    println!("base62    {}", uid.base62());
+}
+
+#[cfg(not(quid_uid32))]
+fn parse_layout(value: &str) -> Layout {
+    let bits: Vec<u32> = value.split(',').map(|n| parse_num("--layout", n)).collect();
+    let layout = match bits[..] {
+        [timestamp_bits, node_bits, sequence_bits] => Layout { timestamp_bits, node_bits, sequence_bits },
+        _ => fail("--layout takes three bit counts: timestamp, node and sequence"),
+    };
+    if layout.timestamp_bits == 0 || layout.sequence_bits == 0 || layout.timestamp_bits + layout.node_bits + layout.sequence_bits > 64 {
+        fail(&format!("invalid layout {}", value));
+    }
+    layout
+}
+
+// milliseconds since the Unix epoch as an RFC 3339 UTC time
+fn utc(millis: u64) -> String {
+    let secs = millis / 1000;
+    let days = secs / 86_400;
+    let rem = secs % 86_400;
+    // Howard Hinnant's civil_from_days, for days since 1970-01-01
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u64;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_uid, utc};
+    use quid::{Encoding, UID};
+
+    #[test]
+    fn test_utc() {
+        assert_eq!(utc(0), "1970-01-01T00:00:00.000Z");
+        #[cfg(not(quid_uid32))]
+        assert_eq!(utc(quid::DEFAULT_EPOCH), "2020-01-01T00:00:00.000Z");
+        assert_eq!(utc(951_782_400_123), "2000-02-29T00:00:00.123Z");
+        assert_eq!(utc(1_646_006_399_999), "2022-02-27T23:59:59.999Z");
+    }
+
+    #[test]
+    fn test_format_round_trips() {
+        let uid = UID::parse_as("512", Encoding::Decimal).unwrap();
+        assert_eq!(format_uid(&uid, Encoding::Hex, false), "0x200");
+        assert_eq!(format_uid(&uid, Encoding::Base32, true), "G0Z");
+        for encoding in [Encoding::Decimal, Encoding::Hex, Encoding::Base32, Encoding::Base62] {
+            assert_eq!(UID::parse_as(&format_uid(&uid, encoding, false), encoding), Ok(uid));
+        }
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A source of wall-clock time for the time-based generators.
+pub trait Clock: Sync {
+    /// Milliseconds since the Unix epoch.
+    fn now_millis(&self) -> u64;
+
+    /// Called in a loop while a generator waits for the clock to pass `millis`.
+    /// Sleeps until it should have by default.
+    fn wait_past(&self, millis: u64) {
+        let ahead = (millis + 1).saturating_sub(self.now_millis());
+        std::thread::sleep(Duration::from_millis(ahead));
+    }
+}
+
+/// [`SystemTime`], 0 if it is before the Unix epoch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64)
+    }
+}
+
+/// What a time-based generator does when the clock is behind the last timestamp
+/// it handed out, e.g. after an NTP step or a VM migration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockRegression {
+    /// Block until the clock catches up.
+    Wait,
+    /// Keep counting from the last timestamp, carrying sequence overflows into the
+    /// timestamp, so IDs run ahead of the clock until it catches up.
+    /// This also means a full millisecond never waits.
+    Borrow,
+    /// Return [`ClockError::Backwards`](crate::ClockError::Backwards).
+    Error,
+}
+
+// only moves when told to, or past the awaited millisecond on a wait
+#[cfg(test)]
+pub(crate) struct FakeClock {
+    now: AtomicU64,
+    waits: AtomicU64,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub(crate) const fn at(millis: u64) -> Self {
+        FakeClock { now: AtomicU64::new(millis), waits: AtomicU64::new(0) }
+    }
+
+    pub(crate) fn set(&self, millis: u64) {
+        self.now.store(millis, Ordering::Relaxed);
+    }
+
+    pub(crate) fn waits(&self) -> u64 {
+        self.waits.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now_millis(&self) -> u64 {
+        self.now.load(Ordering::Relaxed)
+    }
+
+    fn wait_past(&self, millis: u64) {
+        self.waits.fetch_add(1, Ordering::Relaxed);
+        self.now.fetch_max(millis + 1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, SystemClock};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_wait_past_sleeps() {
+        let start = Instant::now();
+        SystemClock.wait_past(SystemClock.now_millis() + 20);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
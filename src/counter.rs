@@ -0,0 +1,252 @@
+// without native atomics as wide as `UidTy`, in order of preference:
+// portable-atomic, a critical section, a spinlock
+#[cfg(quid_native_atomic)]
+pub(crate) use native::AtomicUid;
+
+#[cfg(all(not(quid_native_atomic), feature = "portable-atomic"))]
+pub(crate) use portable::AtomicUid;
+
+#[cfg(all(not(quid_native_atomic), not(feature = "portable-atomic"), feature = "critical-section"))]
+pub(crate) use cs::AtomicUid;
+
+#[cfg(all(not(quid_native_atomic), not(feature = "portable-atomic"), not(feature = "critical-section")))]
+pub(crate) use spin::AtomicUid;
+
+#[cfg(all(
+    not(quid_native_atomic),
+    not(feature = "portable-atomic"),
+    not(feature = "critical-section"),
+    not(target_has_atomic = "8")
+))]
+compile_error!("this target has no compare-and-swap, enable the `portable-atomic` or `critical-section` feature of quid");
+
+// the fallbacks keep their locks in process memory
+#[cfg(all(feature = "shm", unix, not(quid_native_atomic)))]
+compile_error!("the `shm` feature needs native atomics as wide as `UidTy`");
+
+#[cfg(quid_native_atomic)]
+mod native {
+    use core::sync::atomic::Ordering;
+
+    use crate::UidTy;
+
+    #[cfg(not(quid_uid32))]
+    type Atomic = core::sync::atomic::AtomicU64;
+    #[cfg(quid_uid32)]
+    type Atomic = core::sync::atomic::AtomicU32;
+
+    /// A `UidTy` that can be shared between threads.
+    #[repr(transparent)]
+    pub(crate) struct AtomicUid(Atomic);
+
+    impl AtomicUid {
+        pub(crate) const fn new(val: UidTy) -> Self {
+            AtomicUid(Atomic::new(val))
+        }
+
+        // `ptr` has to be aligned and valid for as long as the reference is used
+        #[cfg(all(feature = "shm", unix))]
+        pub(crate) unsafe fn from_ptr<'a>(ptr: *mut UidTy) -> &'a AtomicUid {
+            &*(ptr as *const AtomicUid)
+        }
+
+        pub(crate) fn load(&self, order: Ordering) -> UidTy {
+            self.0.load(order)
+        }
+
+        pub(crate) fn store(&self, val: UidTy, order: Ordering) {
+            self.0.store(val, order)
+        }
+
+        pub(crate) fn fetch_update(
+            &self,
+            order: Ordering,
+            f: impl FnMut(UidTy) -> Option<UidTy>,
+        ) -> Result<UidTy, UidTy> {
+            self.0.fetch_update(order, Ordering::Relaxed, f)
+        }
+    }
+}
+
+#[cfg(all(not(quid_native_atomic), feature = "portable-atomic"))]
+mod portable {
+    use core::sync::atomic::Ordering;
+
+    use crate::UidTy;
+
+    #[cfg(not(quid_uid32))]
+    type Atomic = portable_atomic::AtomicU64;
+    #[cfg(quid_uid32)]
+    type Atomic = portable_atomic::AtomicU32;
+
+    pub(crate) struct AtomicUid(Atomic);
+
+    impl AtomicUid {
+        pub(crate) const fn new(val: UidTy) -> Self {
+            AtomicUid(Atomic::new(val))
+        }
+
+        pub(crate) fn load(&self, order: Ordering) -> UidTy {
+            self.0.load(order)
+        }
+
+        pub(crate) fn store(&self, val: UidTy, order: Ordering) {
+            self.0.store(val, order)
+        }
+
+        pub(crate) fn fetch_update(
+            &self,
+            order: Ordering,
+            f: impl FnMut(UidTy) -> Option<UidTy>,
+        ) -> Result<UidTy, UidTy> {
+            self.0.fetch_update(order, Ordering::Relaxed, f)
+        }
+    }
+}
+
+// a critical section orders everything, so the memory orderings don't matter here
+#[cfg(all(not(quid_native_atomic), not(feature = "portable-atomic"), feature = "critical-section"))]
+mod cs {
+    use core::{cell::Cell, sync::atomic::Ordering};
+
+    use critical_section::Mutex;
+
+    use crate::UidTy;
+
+    pub(crate) struct AtomicUid(Mutex<Cell<UidTy>>);
+
+    impl AtomicUid {
+        pub(crate) const fn new(val: UidTy) -> Self {
+            AtomicUid(Mutex::new(Cell::new(val)))
+        }
+
+        pub(crate) fn load(&self, _order: Ordering) -> UidTy {
+            critical_section::with(|cs| self.0.borrow(cs).get())
+        }
+
+        pub(crate) fn store(&self, val: UidTy, _order: Ordering) {
+            critical_section::with(|cs| self.0.borrow(cs).set(val))
+        }
+
+        pub(crate) fn fetch_update(
+            &self,
+            _order: Ordering,
+            mut f: impl FnMut(UidTy) -> Option<UidTy>,
+        ) -> Result<UidTy, UidTy> {
+            critical_section::with(|cs| {
+                let cell = self.0.borrow(cs);
+                let cur = cell.get();
+                let new = f(cur).ok_or(cur)?;
+                cell.set(new);
+                Ok(cur)
+            })
+        }
+    }
+}
+
+// always built for tests, so the fallback gets exercised on targets that don't need it
+#[cfg(any(test, all(not(quid_native_atomic), not(feature = "portable-atomic"), not(feature = "critical-section"))))]
+mod spin {
+    use core::{cell::UnsafeCell, hint, sync::atomic::{AtomicBool, Ordering}};
+
+    use crate::UidTy;
+
+    /// Only needs a byte-sized compare-and-swap. The lock is held for a single
+    /// read-modify-write of the value, so the spinning stays short.
+    pub(crate) struct AtomicUid {
+        locked: AtomicBool,
+        val: UnsafeCell<UidTy>,
+    }
+
+    unsafe impl Sync for AtomicUid {}
+
+    struct Guard<'a>(&'a AtomicUid);
+
+    impl Drop for Guard<'_> {
+        fn drop(&mut self) {
+            self.0.locked.store(false, Ordering::Release);
+        }
+    }
+
+    impl AtomicUid {
+        pub(crate) const fn new(val: UidTy) -> Self {
+            AtomicUid {
+                locked: AtomicBool::new(false),
+                val: UnsafeCell::new(val),
+            }
+        }
+
+        fn lock(&self) -> Guard<'_> {
+            while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+                while self.locked.load(Ordering::Relaxed) {
+                    hint::spin_loop();
+                }
+            }
+            Guard(self)
+        }
+
+        pub(crate) fn load(&self, _order: Ordering) -> UidTy {
+            let _guard = self.lock();
+            unsafe { *self.val.get() }
+        }
+
+        pub(crate) fn store(&self, val: UidTy, _order: Ordering) {
+            let _guard = self.lock();
+            unsafe { *self.val.get() = val };
+        }
+
+        pub(crate) fn fetch_update(
+            &self,
+            _order: Ordering,
+            mut f: impl FnMut(UidTy) -> Option<UidTy>,
+        ) -> Result<UidTy, UidTy> {
+            let _guard = self.lock();
+            let cur = unsafe { *self.val.get() };
+            let new = f(cur).ok_or(cur)?;
+            unsafe { *self.val.get() = new };
+            Ok(cur)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spin;
+    use crate::UidTy;
+    use core::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_spin_counter_is_atomic() {
+        const THREADS: usize = 8;
+        const ADDS: UidTy = 10_000;
+
+        let counter = Arc::new(spin::AtomicUid::new(0));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let counter = Arc::clone(&counter);
+                thread::spawn(move || {
+                    for _ in 0..ADDS {
+                        counter.fetch_update(Ordering::Relaxed, |cur| Some(cur + 1)).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().expect("Thread panicked");
+        }
+        assert_eq!(counter.load(Ordering::Relaxed), THREADS as UidTy * ADDS);
+    }
+
+    #[test]
+    fn test_spin_counter_rejected_update() {
+        let counter = spin::AtomicUid::new(5);
+        assert_eq!(counter.fetch_update(Ordering::Relaxed, |_| None), Err(5));
+
+        counter.store(7, Ordering::Relaxed);
+        assert_eq!(counter.fetch_update(Ordering::Relaxed, |cur| Some(cur * 2)), Ok(7));
+        assert_eq!(counter.load(Ordering::Relaxed), 14);
+    }
+}
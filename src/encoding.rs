@@ -0,0 +1,283 @@
+use core::str::FromStr;
+
+use crate::{ParseUidError, UidTy, UID};
+
+pub(crate) const DECIMAL: &[u8] = b"0123456789";
+pub(crate) const HEX: &[u8] = b"0123456789abcdef";
+// Crockford's alphabet, no I, L, O or U
+pub(crate) const BASE32: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// the extra symbols for Crockford's mod 37 check symbol
+pub(crate) const BASE32_CHECK: &[u8] = b"*~$=U";
+// sorts the same way as the numbers it encodes, as long as the width is fixed
+pub(crate) const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// The textual forms a UID can be printed in and parsed from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Decimal,
+    /// Either case, with or without a `0x` prefix.
+    Hex,
+    /// Crockford base32, either case. `O` is read as `0`, `I` and `L` as `1`, and hyphens are ignored.
+    Base32,
+    Base62,
+}
+
+/// How many digits `UidTy::MAX` has in the given base, the width of the `{:#}` forms.
+#[cfg(any(feature = "fmt", feature = "std"))]
+pub(crate) const fn max_digits(base: UidTy) -> usize {
+    let mut n = UidTy::MAX;
+    let mut digits = 0;
+    while n > 0 {
+        n /= base;
+        digits += 1;
+    }
+    digits
+}
+
+/// Writes `n` into the end of `buf`, zero-padded to `min_width`, and returns where it starts.
+#[cfg(any(feature = "fmt", feature = "std", feature = "serde"))]
+pub(crate) fn encode(mut n: UidTy, alphabet: &[u8], min_width: usize, buf: &mut [u8]) -> usize {
+    let base = alphabet.len() as UidTy;
+    let mut pos = buf.len();
+    loop {
+        pos -= 1;
+        buf[pos] = alphabet[(n % base) as usize];
+        n /= base;
+        if n == 0 {
+            break;
+        }
+    }
+    while buf.len() - pos < min_width {
+        pos -= 1;
+        buf[pos] = alphabet[0];
+    }
+    pos
+}
+
+pub(crate) fn base32_check_symbol(n: UidTy) -> u8 {
+    let i = (n % 37) as usize;
+    if i < BASE32.len() {
+        BASE32[i]
+    } else {
+        BASE32_CHECK[i - BASE32.len()]
+    }
+}
+
+pub(crate) fn digit_value(encoding: Encoding, c: u8) -> Option<u8> {
+    let alphabet = match encoding {
+        Encoding::Decimal => DECIMAL,
+        Encoding::Hex => HEX,
+        Encoding::Base32 => BASE32,
+        Encoding::Base62 => BASE62,
+    };
+    let c = match encoding {
+        Encoding::Hex => c.to_ascii_lowercase(),
+        Encoding::Base32 => match c.to_ascii_uppercase() {
+            b'O' => b'0',
+            b'I' | b'L' => b'1',
+            c => c,
+        },
+        _ => c,
+    };
+    alphabet.iter().position(|&d| d == c).map(|pos| pos as u8)
+}
+
+/// The integers digits are parsed into: `UidTy`, and `u128` for ULIDs.
+pub(crate) trait Accumulator: Sized {
+    const ZERO: Self;
+
+    /// `self * base + digit`, `None` on overflow.
+    fn push_digit(self, base: u8, digit: u8) -> Option<Self>;
+}
+
+macro_rules! impl_accumulator {
+    ($($ty:ty),*) => {
+        $(
+            impl Accumulator for $ty {
+                const ZERO: Self = 0;
+
+                fn push_digit(self, base: u8, digit: u8) -> Option<Self> {
+                    self.checked_mul(base.into())?.checked_add(digit.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_accumulator!(u32, u64, u128);
+
+pub(crate) fn parse_digits<N: Accumulator>(s: &str, encoding: Encoding) -> Result<N, ParseUidError> {
+    let base: u8 = match encoding {
+        Encoding::Decimal => 10,
+        Encoding::Hex => 16,
+        Encoding::Base32 => 32,
+        Encoding::Base62 => 62,
+    };
+    let mut n = N::ZERO;
+    let mut empty = true;
+    for (index, c) in s.char_indices() {
+        if c == '-' && encoding == Encoding::Base32 {
+            continue;
+        }
+        empty = false;
+        let digit = u8::try_from(c)
+            .ok()
+            .and_then(|c| digit_value(encoding, c))
+            .ok_or(ParseUidError::InvalidChar { index, ch: c })?;
+        n = n.push_digit(base, digit).ok_or(ParseUidError::Overflow)?;
+    }
+    // hyphens alone aren't an ID
+    if empty {
+        return Err(ParseUidError::Empty);
+    }
+    Ok(n)
+}
+
+fn strip_hex_prefix(s: &str) -> Option<&str> {
+    s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"))
+}
+
+impl UID {
+    /// Parses any of the forms the formatting impls print, including the zero-padded `{:#}` ones.
+    pub fn parse_as(s: &str, encoding: Encoding) -> Result<UID, ParseUidError> {
+        let s = match encoding {
+            Encoding::Hex => strip_hex_prefix(s).unwrap_or(s),
+            _ => s,
+        };
+        parse_digits(s, encoding).map(UID)
+    }
+}
+
+impl UID {
+    /// Crockford base32, zero-padded to a fixed width (13 characters for 64-bit IDs) so the
+    /// strings sort like the IDs. [`UID::base32`] does the same without allocating.
+    #[cfg(feature = "std")]
+    pub fn to_base32(&self) -> String {
+        let mut buf = [0u8; UidTy::BITS as usize];
+        let pos = encode(self.0, BASE32, max_digits(32), &mut buf);
+        buf[pos..].iter().map(|&c| c as char).collect()
+    }
+
+    /// Reads Crockford base32 in either case, with or without padding.
+    pub fn from_base32(s: &str) -> Result<UID, ParseUidError> {
+        UID::parse_as(s, Encoding::Base32)
+    }
+
+    /// Like [`UID::from_base32`], but the last character has to be the check symbol,
+    /// as written by `uid.base32().with_check()`.
+    pub fn from_base32_checked(s: &str) -> Result<UID, ParseUidError> {
+        let (index, ch) = s.char_indices().next_back().ok_or(ParseUidError::Empty)?;
+        let uid = UID::from_base32(&s[..index])?;
+        let expected = base32_check_symbol(uid.0);
+        let found = u8::try_from(ch)
+            .ok()
+            .map(|c| match c.to_ascii_uppercase() {
+                b'O' => b'0',
+                b'I' | b'L' => b'1',
+                c => c,
+            })
+            .filter(|&c| BASE32.contains(&c) || BASE32_CHECK.contains(&c))
+            .ok_or(ParseUidError::InvalidChar { index, ch })?;
+        if found == expected {
+            Ok(uid)
+        } else {
+            Err(ParseUidError::CheckSymbol)
+        }
+    }
+}
+
+/// Decimal, or hex with a `0x` prefix. Base32 and base62 can't be told apart
+/// from decimal (or each other), use [`UID::parse_as`] for those.
+impl FromStr for UID {
+    type Err = ParseUidError;
+
+    fn from_str(s: &str) -> Result<UID, ParseUidError> {
+        match strip_hex_prefix(s) {
+            Some(hex) => UID::parse_as(hex, Encoding::Hex),
+            None => UID::parse_as(s, Encoding::Decimal),
+        }
+    }
+}
+
+impl TryFrom<&str> for UID {
+    type Error = ParseUidError;
+
+    fn try_from(s: &str) -> Result<UID, ParseUidError> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+    use crate::{ParseUidError, UidTy, UID};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("".parse::<UID>(), Err(ParseUidError::Empty));
+        assert_eq!("0x".parse::<UID>(), Err(ParseUidError::Empty));
+        assert_eq!("12a".parse::<UID>(), Err(ParseUidError::InvalidChar { index: 2, ch: 'a' }));
+        assert_eq!(UID::parse_as("0U", Encoding::Base32), Err(ParseUidError::InvalidChar { index: 1, ch: 'U' }));
+        assert_eq!("99999999999999999999999".parse::<UID>(), Err(ParseUidError::Overflow));
+        assert_eq!(UID::try_from("0x1F"), Ok(UID(31)));
+        assert_eq!(UID::parse_as("g0", Encoding::Base32), Ok(UID(512)));
+    }
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(UID::from_base32("1-oIl"), UID::from_base32("1011"));
+        assert_eq!(UID::from_base32("ZZ"), Ok(UID(1023)));
+        assert_eq!(UID::from_base32("G0U"), Err(ParseUidError::InvalidChar { index: 2, ch: 'U' }));
+        assert_eq!(UID::from_base32("---"), Err(ParseUidError::Empty));
+        assert_eq!(UID::from_base32_checked("-0"), Err(ParseUidError::Empty));
+
+        // 36 % 37 is the last check symbol
+        assert_eq!(UID(36).base32().with_check().to_string(), "14U");
+        assert_eq!(UID::from_base32_checked("14u"), Ok(UID(36)));
+        assert_eq!(UID::from_base32_checked("14$"), Err(ParseUidError::CheckSymbol));
+        assert_eq!(UID::from_base32_checked("14#"), Err(ParseUidError::InvalidChar { index: 2, ch: '#' }));
+        assert_eq!(UID::from_base32_checked("1"), Err(ParseUidError::Empty));
+
+        #[cfg(not(quid_uid32))]
+        assert_eq!(UID(512).to_base32(), "00000000000G0");
+        assert_eq!(UID(UidTy::MAX).to_base32().len(), UID(1).to_base32().len());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_decimal_round_trip(n: UidTy) {
+            let uid = UID(n);
+            prop_assert_eq!(uid.to_string().parse::<UID>(), Ok(uid));
+            prop_assert_eq!(format!("{:#}", uid).parse::<UID>(), Ok(uid));
+        }
+
+        #[test]
+        fn prop_hex_round_trip(n: UidTy) {
+            let uid = UID(n);
+            prop_assert_eq!(format!("{:#x}", uid).parse::<UID>(), Ok(uid));
+            prop_assert_eq!(UID::parse_as(&format!("{:X}", uid), Encoding::Hex), Ok(uid));
+        }
+
+        #[test]
+        fn prop_base32_round_trip(n: UidTy) {
+            let uid = UID(n);
+            prop_assert_eq!(UID::parse_as(&uid.base32().to_string(), Encoding::Base32), Ok(uid));
+            prop_assert_eq!(UID::parse_as(&format!("{:#}", uid.base32()), Encoding::Base32), Ok(uid));
+        }
+
+        #[test]
+        fn prop_base32_checked_round_trip(n: UidTy) {
+            let uid = UID(n);
+            prop_assert_eq!(UID::from_base32(&uid.to_base32()), Ok(uid));
+            prop_assert_eq!(UID::from_base32_checked(&uid.base32().with_check().to_string()), Ok(uid));
+        }
+
+        #[test]
+        fn prop_base62_round_trip(n: UidTy) {
+            let uid = UID(n);
+            prop_assert_eq!(UID::parse_as(&uid.base62().to_string(), Encoding::Base62), Ok(uid));
+            prop_assert_eq!(UID::parse_as(&format!("{:#}", uid.base62()), Encoding::Base62), Ok(uid));
+        }
+    }
+}
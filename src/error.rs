@@ -0,0 +1,70 @@
+#[cfg(feature = "fmt")]
+use nostd::fmt;
+
+/// Returned when a generator has handed out every ID below its limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UidExhausted;
+
+#[cfg(feature = "fmt")]
+impl fmt::Display for UidExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UID space exhausted")
+    }
+}
+
+#[cfg(all(feature = "std", feature = "fmt"))]
+impl std::error::Error for UidExhausted {}
+
+/// Why a time-based generator couldn't hand out an ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClockError {
+    /// The clock is past what fits into the ID's timestamp bits.
+    OutOfRange,
+    /// The clock is behind the last timestamp handed out by this many milliseconds.
+    Backwards { millis: u64 },
+}
+
+#[cfg(feature = "fmt")]
+impl fmt::Display for ClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClockError::OutOfRange => f.write_str("clock is past the range of the ID's timestamp"),
+            ClockError::Backwards { millis } => write!(f, "clock moved backwards by {}ms", millis),
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "fmt"))]
+impl std::error::Error for ClockError {}
+
+/// Why a string couldn't be parsed as a UID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseUidError {
+    Empty,
+    /// `index` is the byte offset of `ch` in the input.
+    InvalidChar { index: usize, ch: char },
+    /// The number doesn't fit into `UidTy`.
+    Overflow,
+    /// The base32 check symbol doesn't match the rest of the string.
+    CheckSymbol,
+    /// For fixed-width forms like UUIDs, `len` is the length in bytes.
+    InvalidLength { len: usize },
+}
+
+#[cfg(feature = "fmt")]
+impl fmt::Display for ParseUidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseUidError::Empty => f.write_str("cannot parse UID from empty string"),
+            ParseUidError::InvalidChar { index, ch } => write!(f, "invalid character {:?} at index {} in UID", ch, index),
+            ParseUidError::Overflow => f.write_str("UID too large"),
+            ParseUidError::CheckSymbol => f.write_str("UID check symbol mismatch"),
+            ParseUidError::InvalidLength { len } => write!(f, "invalid length {} for UID", len),
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "fmt"))]
+impl std::error::Error for ParseUidError {}
@@ -0,0 +1,122 @@
+use core::str;
+
+use nostd::fmt;
+
+use crate::{
+    encoding::{base32_check_symbol, encode, max_digits, BASE32, BASE62, DECIMAL},
+    UidTy, UID,
+};
+
+/// Displays a UID in Crockford base32. `{:#}` pads it to a fixed width with zeros.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Base32 {
+    uid: UidTy,
+    check: bool,
+}
+
+/// Displays a UID in base62 (`0-9A-Za-z`). `{:#}` pads it to a fixed width with zeros.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Base62(UidTy);
+
+fn write_digits(f: &mut fmt::Formatter<'_>, n: UidTy, alphabet: &[u8], check: Option<u8>) -> fmt::Result {
+    let mut buf = [0u8; UidTy::BITS as usize + 1];
+    let end = buf.len() - check.is_some() as usize;
+    let width = if f.alternate() { max_digits(alphabet.len() as UidTy) } else { 0 };
+    let pos = encode(n, alphabet, width, &mut buf[..end]);
+    if let Some(check) = check {
+        buf[end] = check;
+    }
+    // every alphabet is ASCII
+    f.pad(str::from_utf8(&buf[pos..]).map_err(|_| fmt::Error)?)
+}
+
+impl UID {
+    pub const fn base32(&self) -> Base32 {
+        Base32 { uid: self.0, check: false }
+    }
+
+    pub const fn base62(&self) -> Base62 {
+        Base62(self.0)
+    }
+}
+
+impl fmt::Debug for UID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UID({})", self.0)
+    }
+}
+
+/// Decimal. `{:#}` zero-pads to the width of the largest ID, so IDs line up in columns.
+impl fmt::Display for UID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write_digits(f, self.0, DECIMAL, None)
+        } else {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+impl fmt::LowerHex for UID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for UID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl Base32 {
+    /// Appends Crockford's check symbol, one of the 37 characters `0-9A-Z*~$=U`.
+    pub const fn with_check(self) -> Base32 {
+        Base32 { check: true, ..self }
+    }
+}
+
+impl fmt::Display for Base32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let check = self.check.then(|| base32_check_symbol(self.uid));
+        write_digits(f, self.uid, BASE32, check)
+    }
+}
+
+impl fmt::Display for Base62 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_digits(f, self.0, BASE62, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{UidTy, UID};
+
+    #[test]
+    fn test_display_forms() {
+        let uid = UID(512);
+
+        assert_eq!(format!("{}", uid), "512");
+        assert_eq!(format!("{:?}", uid), "UID(512)");
+        assert_eq!(format!("{:x}", uid), "200");
+        assert_eq!(format!("{:#X}", uid), "0x200");
+        assert_eq!(format!("{:>6}", uid), "   512");
+        #[cfg(not(quid_uid32))]
+        assert_eq!(format!("{:#}", uid), "00000000000000000512");
+    }
+
+    #[test]
+    fn test_alternate_encodings() {
+        assert_eq!(UID(512).base32().to_string(), "G0");
+        assert_eq!(format!("{:>4}", UID(512).base32().with_check()), " G0Z");
+        assert_eq!(UID(61).base62().to_string(), "z");
+        assert_eq!(UID(62).base62().to_string(), "10");
+
+        let max = UID(UidTy::MAX);
+        assert_eq!(format!("{:#}", UID(1).base32()).len(), max.base32().to_string().len());
+        assert_eq!(format!("{:#}", UID(1).base62()).len(), max.base62().to_string().len());
+        #[cfg(not(quid_uid32))]
+        assert_eq!(max.base62().to_string(), "LygHa16AHYF");
+    }
+}
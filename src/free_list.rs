@@ -0,0 +1,201 @@
+use crate::UidTy;
+
+/// Partial blocks given back by exiting threads and dropped caches.
+///
+/// Every slot is claimed with a CAS on its state, so a thread stalled in the
+/// middle of a push or pop only holds up that one slot. With `std` the list grows
+/// by another segment of slots when it is full, without it blocks that don't fit
+/// are dropped. On targets without compare-and-swap nothing is recycled.
+pub(crate) struct FreeList {
+    inner: imp::FreeList,
+}
+
+impl FreeList {
+    pub(crate) const fn new() -> Self {
+        FreeList { inner: imp::FreeList::new() }
+    }
+
+    pub(crate) fn push(&self, base: UidTy, len: UidTy) {
+        if len > 0 {
+            self.inner.push(base, len);
+        }
+    }
+
+    pub(crate) fn pop(&self) -> Option<(UidTy, UidTy)> {
+        self.inner.pop()
+    }
+}
+
+#[cfg(not(target_has_atomic = "8"))]
+mod imp {
+    use crate::UidTy;
+
+    pub(super) struct FreeList;
+
+    impl FreeList {
+        pub(super) const fn new() -> Self {
+            FreeList
+        }
+
+        pub(super) fn push(&self, _base: UidTy, _len: UidTy) {}
+
+        pub(super) fn pop(&self) -> Option<(UidTy, UidTy)> {
+            None
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+mod imp {
+    #[cfg(feature = "std")]
+    use core::{ptr, sync::atomic::AtomicPtr};
+    use core::{cell::UnsafeCell, sync::atomic::{AtomicU8, AtomicUsize, Ordering}};
+
+    use crate::UidTy;
+
+    // slots per segment
+    const FREE_SLOTS: usize = 16;
+
+    const EMPTY: u8 = 0;
+    const BUSY: u8 = 1;
+    const FULL: u8 = 2;
+
+    pub(super) struct FreeList {
+        len: AtomicUsize,
+        head: Segment,
+    }
+
+    // segments are only ever appended, and freed with the list
+    struct Segment {
+        slots: [FreeSlot; FREE_SLOTS],
+        #[cfg(feature = "std")]
+        next: AtomicPtr<Segment>,
+    }
+
+    struct FreeSlot {
+        state: AtomicU8,
+        // only touched by whoever moved `state` to BUSY
+        block: UnsafeCell<(UidTy, UidTy)>,
+    }
+
+    unsafe impl Sync for FreeSlot {}
+
+    impl FreeSlot {
+        const fn new() -> Self {
+            FreeSlot {
+                state: AtomicU8::new(EMPTY),
+                block: UnsafeCell::new((0, 0)),
+            }
+        }
+    }
+
+    impl Segment {
+        const fn new() -> Self {
+            Segment {
+                slots: [const { FreeSlot::new() }; FREE_SLOTS],
+                #[cfg(feature = "std")]
+                next: AtomicPtr::new(ptr::null_mut()),
+            }
+        }
+
+        #[cfg(feature = "std")]
+        fn next(&self) -> Option<&Segment> {
+            unsafe { self.next.load(Ordering::Acquire).as_ref() }
+        }
+
+        #[cfg(not(feature = "std"))]
+        fn next(&self) -> Option<&Segment> {
+            None
+        }
+
+        // the segment after this one, appending one if there is none yet
+        #[cfg(feature = "std")]
+        fn grow(&self) -> Option<&Segment> {
+            if let Some(next) = self.next() {
+                return Some(next);
+            }
+            let new = Box::into_raw(Box::new(Segment::new()));
+            match self.next.compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => unsafe { new.as_ref() },
+                Err(winner) => {
+                    drop(unsafe { Box::from_raw(new) });
+                    unsafe { winner.as_ref() }
+                }
+            }
+        }
+
+        #[cfg(not(feature = "std"))]
+        fn grow(&self) -> Option<&Segment> {
+            None
+        }
+    }
+
+    impl FreeList {
+        pub(super) const fn new() -> Self {
+            FreeList { len: AtomicUsize::new(0), head: Segment::new() }
+        }
+
+        pub(super) fn push(&self, base: UidTy, len: UidTy) {
+            let mut segment = Some(&self.head);
+            while let Some(seg) = segment {
+                for slot in &seg.slots {
+                    if slot.state.compare_exchange(EMPTY, BUSY, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                        unsafe { *slot.block.get() = (base, len) };
+                        slot.state.store(FULL, Ordering::Release);
+                        self.len.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    }
+                }
+                segment = seg.grow();
+            }
+        }
+
+        pub(super) fn pop(&self) -> Option<(UidTy, UidTy)> {
+            // only a hint, but it keeps the common case of an empty list to a single load
+            if self.len.load(Ordering::Relaxed) == 0 {
+                return None;
+            }
+            let mut segment = Some(&self.head);
+            while let Some(seg) = segment {
+                for slot in &seg.slots {
+                    if slot.state.compare_exchange(FULL, BUSY, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                        let block = unsafe { *slot.block.get() };
+                        slot.state.store(EMPTY, Ordering::Release);
+                        self.len.fetch_sub(1, Ordering::Relaxed);
+                        return Some(block);
+                    }
+                }
+                segment = seg.next();
+            }
+            None
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl Drop for FreeList {
+        fn drop(&mut self) {
+            let mut next = *self.head.next.get_mut();
+            while !next.is_null() {
+                let mut segment = unsafe { Box::from_raw(next) };
+                next = *segment.next.get_mut();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FreeList;
+    use crate::UidTy;
+
+    #[test]
+    fn test_grows_past_a_segment() {
+        let list = FreeList::new();
+        for base in 1..=40 {
+            list.push(base * 10, 5);
+        }
+        let mut popped: Vec<(UidTy, UidTy)> = core::iter::from_fn(|| list.pop()).collect();
+        popped.sort_unstable();
+        assert_eq!(popped, (1..=40).map(|base| (base * 10, 5)).collect::<Vec<_>>());
+    }
+}
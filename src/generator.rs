@@ -0,0 +1,718 @@
+use core::{
+    ptr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{counter::AtomicUid, free_list::FreeList, UidExhausted, UidRange, UidTy, UID};
+
+#[cfg(feature = "std")]
+mod persist;
+#[cfg(all(feature = "shm", unix))]
+mod shared;
+#[cfg(feature = "std")]
+mod thread_cache;
+#[cfg(feature = "std")]
+pub(crate) use thread_cache::{SlotCache, SlotEntry, SlotIndex};
+#[cfg(feature = "std")]
+pub use persist::{HighWaterMark, DEFAULT_CHUNK};
+
+pub const DEFAULT_BLOCK_SIZE: UidTy = 512;
+
+// adaptive blocks double after this many refills in a row during which no other thread reserved anything
+const GROW_AFTER: u32 = 2;
+// adaptive blocks halve when other threads reserved this many times our block size since our last refill
+const SHRINK_RATIO: UidTy = 8;
+
+/// How many IDs a thread reserves from a generator's counter at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockSize {
+    Fixed(UidTy),
+    /// Starts every thread at `min`. Threads that keep exhausting their block while
+    /// the rest of the process is quiet get bigger blocks, threads that refill
+    /// rarely compared to the others get smaller ones.
+    Adaptive { min: UidTy, max: UidTy },
+}
+
+/// What a generator does once its counter reaches its limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exhaustion {
+    /// `try_*` methods return [`UidExhausted`], the infallible ones panic.
+    Error,
+    Panic,
+    /// Without `std` this panics instead, which aborts on the usual `panic = "abort"` targets.
+    Abort,
+}
+
+impl Exhaustion {
+    // inverse of `as u8`
+    fn from_u8(policy: u8) -> Self {
+        match policy {
+            0 => Exhaustion::Error,
+            1 => Exhaustion::Panic,
+            _ => Exhaustion::Abort,
+        }
+    }
+}
+
+/// Which order a generator hands out IDs in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Monotonicity {
+    /// The fastest mode, IDs within a thread's block count downwards.
+    /// This is the only mode that recycles the blocks of exited threads.
+    Unordered,
+    /// IDs from any single thread are strictly increasing.
+    PerThread,
+    /// If handing out one ID happens-before handing out another, the first one is smaller.
+    /// This bypasses the thread cache and does one atomic RMW on the shared counter per ID.
+    Global,
+}
+
+/// An independent UID space with its own counter and per-thread block cache.
+///
+/// Generators are meant to live in a `static`, which is why handing out IDs
+/// needs a `&'static` reference.
+///
+/// Without the `std` feature there are no per-thread caches: every ID is taken
+/// straight from the counter unless the caller keeps a [`UidCache`] around. The
+/// generator also only keeps 16 given back blocks for reuse then, the IDs left in
+/// any further ones are lost.
+pub struct UidGenerator {
+    next: AtomicUid,
+    #[cfg(feature = "std")]
+    slot: thread_cache::SlotIndex,
+    min_block: AtomicUid,
+    max_block: AtomicUid,
+    limit: UidTy,
+    // an `Exhaustion`, atomic so the global generator's can be changed
+    exhaustion: AtomicU8,
+    monotonicity: Monotonicity,
+    free: FreeList,
+    // nothing at or above this gets handed out before it has been written to `mark`
+    #[cfg(feature = "std")]
+    ceiling: AtomicUid,
+    #[cfg(feature = "std")]
+    mark: std::sync::OnceLock<HighWaterMark>,
+    // replaces `next` once attached to a shared memory segment
+    #[cfg(all(feature = "shm", unix))]
+    shared: std::sync::OnceLock<&'static AtomicUid>,
+}
+
+/// A block of IDs owned by the caller instead of a thread, e.g. one per core
+/// or per interrupt context in a kernel.
+///
+/// Whatever is left of the block is given back to the generator on drop.
+pub struct UidCache(Block);
+
+#[derive(Clone, Copy)]
+struct Block {
+    owner: Option<&'static UidGenerator>,
+    base: UidTy,
+    len: UidTy,
+    rem: UidTy,
+    // adaptive block size state
+    step: UidTy,
+    seen: UidTy,
+    streak: u32,
+}
+
+impl Block {
+    const EMPTY: Block = Block { owner: None, base: 0, len: 0, rem: 0, step: 0, seen: 0, streak: 0 };
+
+    fn is_owned_by(&self, gen: &UidGenerator) -> bool {
+        self.owner.is_some_and(|owner| ptr::eq(owner, gen))
+    }
+
+    fn give_back(&self) {
+        if let Some(owner) = self.owner {
+            if owner.monotonicity == Monotonicity::Unordered {
+                owner.free.push(self.base, self.rem);
+            }
+        }
+    }
+}
+
+impl UidCache {
+    pub const fn new() -> Self {
+        UidCache(Block::EMPTY)
+    }
+}
+
+impl Default for UidCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for UidCache {
+    fn drop(&mut self) {
+        self.0.give_back();
+    }
+}
+
+impl BlockSize {
+    const fn bounds(self) -> (UidTy, UidTy) {
+        let (min, max) = match self {
+            BlockSize::Fixed(n) => (n, n),
+            BlockSize::Adaptive { min, max } => (min, max),
+        };
+        assert!(min > 0 && min <= max, "invalid block size");
+        (min, max)
+    }
+}
+
+impl UidGenerator {
+    pub const fn new() -> Self {
+        UidGenerator {
+            // 0 is never handed out, so it stays free as a sentinel and for `NonZero` flavours
+            next: AtomicUid::new(1),
+            #[cfg(feature = "std")]
+            slot: thread_cache::SlotIndex::new(),
+            min_block: AtomicUid::new(DEFAULT_BLOCK_SIZE),
+            max_block: AtomicUid::new(DEFAULT_BLOCK_SIZE),
+            limit: UidTy::MAX,
+            exhaustion: AtomicU8::new(Exhaustion::Error as u8),
+            monotonicity: Monotonicity::Unordered,
+            free: FreeList::new(),
+            #[cfg(feature = "std")]
+            ceiling: AtomicUid::new(UidTy::MAX),
+            #[cfg(feature = "std")]
+            mark: std::sync::OnceLock::new(),
+            #[cfg(all(feature = "shm", unix))]
+            shared: std::sync::OnceLock::new(),
+        }
+    }
+
+    pub const fn with_block_size(mut self, size: BlockSize) -> Self {
+        let (min, max) = size.bounds();
+        self.min_block = AtomicUid::new(min);
+        self.max_block = AtomicUid::new(max);
+        self
+    }
+
+    /// IDs handed out are always below `limit`. Defaults to `UidTy::MAX`.
+    /// The lowest ID a generator hands out is 1.
+    pub const fn with_limit(mut self, limit: UidTy) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub const fn with_exhaustion(mut self, policy: Exhaustion) -> Self {
+        self.exhaustion = AtomicU8::new(policy as u8);
+        self
+    }
+
+    pub const fn with_monotonicity(mut self, monotonicity: Monotonicity) -> Self {
+        self.monotonicity = monotonicity;
+        self
+    }
+
+    pub fn block_size(&self) -> BlockSize {
+        let (min, max) = self.block_bounds();
+        if min == max {
+            BlockSize::Fixed(min)
+        } else {
+            BlockSize::Adaptive { min, max }
+        }
+    }
+
+    /// Only affects blocks reserved after the call, threads finish their current block first.
+    pub fn set_block_size(&self, size: BlockSize) {
+        let (min, max) = size.bounds();
+        self.min_block.store(min, Ordering::Relaxed);
+        self.max_block.store(max, Ordering::Relaxed);
+    }
+
+    pub fn exhaustion(&self) -> Exhaustion {
+        Exhaustion::from_u8(self.exhaustion.load(Ordering::Relaxed))
+    }
+
+    pub fn set_exhaustion(&self, policy: Exhaustion) {
+        self.exhaustion.store(policy as u8, Ordering::Relaxed);
+    }
+
+    /// Moves the counter up to `min` if it is below, so newly reserved blocks start at `min` or above.
+    /// Never moves it backwards, so concurrent calls end up at the highest `min`.
+    ///
+    /// IDs already sitting in thread blocks, caller caches or the free list are still handed out.
+    pub fn resume_from(&self, min: UidTy) {
+        let _ = self.counter().fetch_update(Ordering::AcqRel, |cur| (cur < min).then_some(min));
+    }
+
+    /// Every ID handed out so far, or reserved in a block, is below this.
+    pub fn high_water_mark(&self) -> UidTy {
+        self.counter().load(Ordering::Acquire)
+    }
+
+    pub fn next_uid(&'static self) -> UID {
+        UID(self.next_raw())
+    }
+
+    pub fn try_next_uid(&'static self) -> Result<UID, UidExhausted> {
+        self.try_next_raw().map(UID)
+    }
+
+    pub fn next_uid_in(&'static self, cache: &mut UidCache) -> UID {
+        match self.try_next_uid_in(cache) {
+            Ok(uid) => uid,
+            Err(_) => self.panic_exhausted(),
+        }
+    }
+
+    pub fn try_next_uid_in(&'static self, cache: &mut UidCache) -> Result<UID, UidExhausted> {
+        if self.monotonicity == Monotonicity::Global {
+            return self.claim(1, Ordering::AcqRel).map(|(base, _)| UID(base));
+        }
+        self.take(&mut cache.0).map(UID)
+    }
+
+    pub(crate) fn next_raw(&'static self) -> UidTy {
+        match self.try_next_raw() {
+            Ok(raw) => raw,
+            Err(_) => self.panic_exhausted(),
+        }
+    }
+
+    pub(crate) fn try_next_raw(&'static self) -> Result<UidTy, UidExhausted> {
+        if self.monotonicity == Monotonicity::Global {
+            return self.claim(1, Ordering::AcqRel).map(|(base, _)| base);
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(res) = self.with_thread_block(|block| self.take(block)) {
+            return res;
+        }
+        self.claim(1, Ordering::Relaxed).map(|(base, _)| base)
+    }
+
+    pub fn reserve(&'static self, n: UidTy) -> UidRange {
+        match self.try_reserve(n) {
+            Ok(range) => range,
+            Err(_) => self.panic_exhausted(),
+        }
+    }
+
+    /// Claims `n` contiguous IDs, taken from the calling thread's block if it still has enough left.
+    pub fn try_reserve(&'static self, n: UidTy) -> Result<UidRange, UidExhausted> {
+        if self.monotonicity == Monotonicity::Global {
+            return self.claim_exact(n, Ordering::AcqRel);
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(res) = self.with_thread_block(|block| self.take_range(block, n)) {
+            return res;
+        }
+        self.claim_exact(n, Ordering::Relaxed)
+    }
+
+    pub fn reserve_in(&'static self, cache: &mut UidCache, n: UidTy) -> UidRange {
+        match self.try_reserve_in(cache, n) {
+            Ok(range) => range,
+            Err(_) => self.panic_exhausted(),
+        }
+    }
+
+    pub fn try_reserve_in(&'static self, cache: &mut UidCache, n: UidTy) -> Result<UidRange, UidExhausted> {
+        if self.monotonicity == Monotonicity::Global {
+            return self.claim_exact(n, Ordering::AcqRel);
+        }
+        self.take_range(&mut cache.0, n)
+    }
+
+    fn take(&'static self, block: &mut Block) -> Result<UidTy, UidExhausted> {
+        if block.rem == 0 || !block.is_owned_by(self) {
+            let fresh = self.refill(block)?;
+            if !block.is_owned_by(self) {
+                block.give_back();
+            }
+            *block = fresh;
+        }
+        block.rem -= 1;
+        Ok(match self.monotonicity {
+            Monotonicity::PerThread => block.base + (block.len - block.rem - 1),
+            _ => block.base + block.rem,
+        })
+    }
+
+    fn take_range(&'static self, block: &mut Block, n: UidTy) -> Result<UidRange, UidExhausted> {
+        if !block.is_owned_by(self) || block.rem < n {
+            if self.monotonicity == Monotonicity::PerThread && block.is_owned_by(self) {
+                // the rest of the block would be lower than the range
+                block.rem = 0;
+            }
+            return self.claim_exact(n, Ordering::Relaxed);
+        }
+
+        let start = match self.monotonicity {
+            Monotonicity::PerThread => block.base + (block.len - block.rem),
+            _ => block.base + block.rem - n,
+        };
+        block.rem -= n;
+        Ok(UidRange::new(start, start + n))
+    }
+
+    fn refill(&'static self, prev: &Block) -> Result<Block, UidExhausted> {
+        #[cfg(feature = "std")]
+        thread_cache::register_dtor();
+
+        let owned = prev.is_owned_by(self);
+        let (min, max) = self.block_bounds();
+        let mut step = min;
+        let mut streak = 0;
+        if min != max && owned {
+            // how much the other threads reserved since our last refill
+            let others = self.counter().load(Ordering::Relaxed).wrapping_sub(prev.seen);
+            step = prev.step;
+            if others == 0 {
+                streak = prev.streak + 1;
+                if streak >= GROW_AFTER {
+                    step = step.saturating_mul(2);
+                    streak = 0;
+                }
+            } else if others >= step.saturating_mul(SHRINK_RATIO) {
+                step /= 2;
+            }
+            step = step.max(min).min(max);
+        }
+
+        if self.monotonicity == Monotonicity::Unordered {
+            if let Some((base, len)) = self.free.pop() {
+                let seen = if owned { prev.seen } else { self.counter().load(Ordering::Relaxed) };
+                return Ok(Block { owner: Some(self), base, len, rem: len, step, seen, streak });
+            }
+        }
+
+        let (base, len) = self.claim(step, Ordering::Relaxed)?;
+        Ok(Block { owner: Some(self), base, len, rem: len, step, seen: base + len, streak })
+    }
+
+    // claims up to `len` IDs from the counter, less if it is about to hit the limit
+    fn claim(&self, len: UidTy, order: Ordering) -> Result<(UidTy, UidTy), UidExhausted> {
+        let limit = self.limit;
+        loop {
+            let ceiling = self.ceiling();
+            match self.counter().fetch_update(order, |cur| {
+                let end = cur + len.min(limit.saturating_sub(cur));
+                (cur < limit && end <= ceiling).then_some(end)
+            }) {
+                Ok(base) => return Ok((base, len.min(limit - base))),
+                Err(cur) if cur < limit => self.raise_ceiling(cur + len.min(limit - cur))?,
+                Err(_) => return Err(self.exhausted()),
+            }
+        }
+    }
+
+    fn claim_exact(&self, n: UidTy, order: Ordering) -> Result<UidRange, UidExhausted> {
+        let limit = self.limit;
+        loop {
+            let ceiling = self.ceiling();
+            match self.counter().fetch_update(order, |cur| {
+                cur.checked_add(n).filter(|&end| end <= limit && end <= ceiling)
+            }) {
+                Ok(start) => return Ok(UidRange::new(start, start + n)),
+                Err(cur) => match cur.checked_add(n).filter(|&end| end <= limit) {
+                    Some(end) => self.raise_ceiling(end)?,
+                    None => return Err(self.exhausted()),
+                },
+            }
+        }
+    }
+
+    fn counter(&self) -> &AtomicUid {
+        #[cfg(all(feature = "shm", unix))]
+        if let Some(shared) = self.shared.get() {
+            return shared;
+        }
+        &self.next
+    }
+
+    // without std nothing is persisted, so there's no ceiling below the limit
+    #[cfg(not(feature = "std"))]
+    fn ceiling(&self) -> UidTy {
+        UidTy::MAX
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn raise_ceiling(&self, _needed: UidTy) -> Result<(), UidExhausted> {
+        Err(self.exhausted())
+    }
+
+    pub(crate) fn exhausted(&self) -> UidExhausted {
+        match self.exhaustion() {
+            Exhaustion::Error => UidExhausted,
+            Exhaustion::Panic => panic!("UID space exhausted"),
+            #[cfg(feature = "std")]
+            Exhaustion::Abort => std::process::abort(),
+            #[cfg(not(feature = "std"))]
+            Exhaustion::Abort => panic!("UID space exhausted"),
+        }
+    }
+
+    #[cold]
+    fn panic_exhausted(&self) -> ! {
+        #[cfg(feature = "std")]
+        self.panic_if_unpersisted();
+        panic!("UID space exhausted")
+    }
+
+    fn block_bounds(&self) -> (UidTy, UidTy) {
+        let min = self.min_block.load(Ordering::Relaxed);
+        let max = self.max_block.load(Ordering::Relaxed);
+        // a concurrent set_block_size may leave us with a mix of old and new bounds
+        (min, max.max(min))
+    }
+}
+
+impl Default for UidGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockSize, Exhaustion, Monotonicity, UidCache, UidGenerator, DEFAULT_BLOCK_SIZE};
+    use crate::{UidExhausted, UidTy};
+    use core::sync::atomic::Ordering;
+    use std::panic;
+    use std::sync::Mutex;
+    use std::thread;
+
+    fn current_block_len(gen: &'static UidGenerator) -> UidTy {
+        gen.with_thread_block(|block| block.step).unwrap()
+    }
+
+    #[test]
+    fn test_generators_are_independent() {
+        static SCENE: UidGenerator = UidGenerator::new();
+        static NET: UidGenerator = UidGenerator::new();
+
+        let a: UidTy = SCENE.next_uid().into();
+        let b: UidTy = SCENE.next_uid().into();
+        let c: UidTy = NET.next_uid().into();
+
+        assert_eq!(a, DEFAULT_BLOCK_SIZE);
+        assert_eq!(b, DEFAULT_BLOCK_SIZE - 1);
+        assert_eq!(c, DEFAULT_BLOCK_SIZE, "NET should not share SCENE's counter");
+    }
+
+    #[test]
+    fn test_many_generators_keep_their_blocks() {
+        static GENS: [UidGenerator; 20] = [const {
+            UidGenerator::new()
+                .with_block_size(BlockSize::Fixed(16))
+                .with_monotonicity(Monotonicity::PerThread)
+        }; 20];
+
+        let ids: Vec<Vec<UidTy>> = (0..4)
+            .map(|_| GENS.iter().map(|gen| gen.next_uid().into()).collect())
+            .collect();
+        for (i, gen) in GENS.iter().enumerate() {
+            let own: Vec<UidTy> = ids.iter().map(|round| round[i]).collect();
+            assert_eq!(own, [1, 2, 3, 4], "generator {i} lost its block");
+            assert_eq!(gen.next.load(Ordering::Relaxed), 17);
+        }
+    }
+
+    #[test]
+    fn test_generator_refills_block() {
+        static GEN: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(4));
+
+        let ids: Vec<UidTy> = (0..6).map(|_| GEN.next_uid().into()).collect();
+        assert_eq!(ids, [4, 3, 2, 1, 8, 7]);
+    }
+
+    #[test]
+    fn test_adaptive_block_size() {
+        static GEN: UidGenerator =
+            UidGenerator::new().with_block_size(BlockSize::Adaptive { min: 2, max: 64 });
+
+        GEN.next_uid();
+        assert_eq!(current_block_len(&GEN), 2);
+
+        // nobody else is allocating, so the block should grow up to the maximum
+        for _ in 0..1000 {
+            GEN.next_uid();
+        }
+        assert_eq!(current_block_len(&GEN), 64);
+
+        // pretend other threads reserve a lot between each of our refills
+        for _ in 0..1000 {
+            GEN.next.fetch_update(Ordering::Relaxed, |cur| Some(cur + 10_000)).unwrap();
+            GEN.next_uid();
+        }
+        assert_eq!(current_block_len(&GEN), 2);
+    }
+
+    #[test]
+    fn test_limit_is_never_crossed() {
+        static GEN: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(4)).with_limit(10);
+
+        let ids: Vec<UidTy> = (0..9).map(|_| GEN.try_next_uid().unwrap().into()).collect();
+        assert_eq!(ids, [4, 3, 2, 1, 8, 7, 6, 5, 9]);
+        assert_eq!(GEN.try_next_uid(), Err(UidExhausted));
+        assert_eq!(GEN.try_next_uid(), Err(UidExhausted));
+    }
+
+    #[test]
+    fn test_counter_does_not_wrap() {
+        static GEN: UidGenerator = UidGenerator::new();
+        GEN.next.store(UidTy::MAX - 3, Ordering::Relaxed);
+
+        let ids: Vec<UidTy> = (0..3).map(|_| GEN.try_next_uid().unwrap().into()).collect();
+        assert_eq!(ids, [UidTy::MAX - 1, UidTy::MAX - 2, UidTy::MAX - 3]);
+        assert_eq!(GEN.try_next_uid(), Err(UidExhausted));
+    }
+
+    #[test]
+    #[should_panic(expected = "UID space exhausted")]
+    fn test_exhaustion_panic_policy() {
+        static GEN: UidGenerator = UidGenerator::new().with_limit(2).with_exhaustion(Exhaustion::Panic);
+
+        GEN.next_uid();
+        let _ = GEN.try_next_uid();
+    }
+
+    #[test]
+    fn test_set_exhaustion() {
+        static GEN: UidGenerator = UidGenerator::new().with_limit(2);
+
+        GEN.next_uid();
+        assert_eq!(GEN.try_next_uid(), Err(UidExhausted));
+        GEN.set_exhaustion(Exhaustion::Panic);
+        assert_eq!(GEN.exhaustion(), Exhaustion::Panic);
+        assert!(panic::catch_unwind(|| GEN.try_next_uid()).is_err());
+    }
+
+    #[test]
+    fn test_per_thread_monotonic() {
+        static GEN: UidGenerator = UidGenerator::new()
+            .with_block_size(BlockSize::Fixed(16))
+            .with_monotonicity(Monotonicity::PerThread);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    let ids: Vec<UidTy> = (0..1000).map(|_| GEN.next_uid().into()).collect();
+                    assert!(ids.windows(2).all(|w| w[0] < w[1]), "IDs went backwards");
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().expect("Thread panicked");
+        }
+    }
+
+    #[test]
+    fn test_global_monotonic() {
+        static GEN: UidGenerator = UidGenerator::new().with_monotonicity(Monotonicity::Global);
+        static LOG: Mutex<Vec<UidTy>> = Mutex::new(Vec::new());
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    for _ in 0..1000 {
+                        // the lock orders the pushes, so the IDs must come out sorted
+                        let mut log = LOG.lock().unwrap();
+                        log.push(GEN.next_uid().into());
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().expect("Thread panicked");
+        }
+
+        let log = LOG.lock().unwrap();
+        assert_eq!(log.len(), 4000);
+        assert!(log.windows(2).all(|w| w[0] < w[1]), "IDs are not in happens-before order");
+    }
+
+    #[test]
+    fn test_reserve_uses_thread_block_when_it_fits() {
+        static GEN: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(16));
+
+        let first: UidTy = GEN.next_uid().into();
+        let range = GEN.reserve(5);
+        assert_eq!((range.start(), range.end()), (11, 16));
+        assert_eq!(first, 16);
+
+        let big = GEN.reserve(100);
+        assert_eq!((big.start(), big.end()), (17, 117));
+        let next: UidTy = GEN.next_uid().into();
+        assert_eq!(next, 10, "the thread block should be kept");
+    }
+
+    #[test]
+    fn test_reserve_keeps_per_thread_order() {
+        static GEN: UidGenerator = UidGenerator::new()
+            .with_block_size(BlockSize::Fixed(16))
+            .with_monotonicity(Monotonicity::PerThread);
+
+        let a: UidTy = GEN.next_uid().into();
+        let small = GEN.reserve(4);
+        let big = GEN.reserve(100);
+        let b: UidTy = GEN.next_uid().into();
+
+        assert_eq!(a, 1);
+        assert_eq!((small.start(), small.end()), (2, 6));
+        assert_eq!((big.start(), big.end()), (17, 117));
+        assert!(b >= big.end());
+    }
+
+    #[test]
+    fn test_reserve_respects_limit() {
+        static GEN: UidGenerator = UidGenerator::new().with_limit(100);
+
+        assert_eq!(GEN.try_reserve(100), Err(UidExhausted));
+        assert_eq!(GEN.try_reserve(99).map(|r| r.len()), Ok(99));
+    }
+
+    #[test]
+    fn test_exited_thread_returns_block() {
+        static GEN: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(16));
+
+        let taken: UidTy = thread::spawn(|| GEN.next_uid().into()).join().unwrap();
+        assert_eq!(taken, 16);
+
+        // this thread has no block yet, so it should pick up the rest of the exited thread's one
+        let ids: Vec<UidTy> = (0..16).map(|_| GEN.next_uid().into()).collect();
+        assert_eq!(ids[..15], (1..16).rev().collect::<Vec<_>>());
+        assert_eq!(ids[15], 32);
+    }
+
+    #[test]
+    fn test_resume_from() {
+        static GEN: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(4));
+
+        GEN.next_uid();
+        assert_eq!(GEN.high_water_mark(), 5);
+
+        let threads: Vec<_> = (1..=8).map(|i| thread::spawn(move || GEN.resume_from(i * 100))).collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        GEN.resume_from(10);
+        assert_eq!(GEN.high_water_mark(), 800);
+
+        // the rest of the current block comes first
+        let ids: Vec<UidTy> = (0..4).map(|_| GEN.next_uid().into()).collect();
+        assert_eq!(ids, [3, 2, 1, 803]);
+    }
+
+    #[test]
+    fn test_caller_provided_cache() {
+        static GEN: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(8));
+
+        let mut cache = UidCache::new();
+        let ids: Vec<UidTy> = (0..3).map(|_| GEN.next_uid_in(&mut cache).into()).collect();
+        assert_eq!(ids, [8, 7, 6]);
+        drop(cache);
+
+        // the dropped cache's leftovers end up in the next one
+        let mut cache = UidCache::new();
+        let ids: Vec<UidTy> = (0..6).map(|_| GEN.next_uid_in(&mut cache).into()).collect();
+        assert_eq!(ids, [5, 4, 3, 2, 1, 16]);
+    }
+}
@@ -0,0 +1,278 @@
+use core::sync::atomic::Ordering;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::UidGenerator;
+use crate::{UidExhausted, UidTy};
+
+pub const DEFAULT_CHUNK: UidTy = 1 << 20;
+
+/// A file recording an upper bound for every ID a generator has handed out, so a
+/// restarted process can resume above it. See [`UidGenerator::persist`].
+///
+/// The mark is written ahead in chunks: a restart skips whatever was left of the
+/// current chunk, and the file is only rewritten once per chunk.
+pub struct HighWaterMark {
+    path: PathBuf,
+    chunk: UidTy,
+    // serialises writes, and the ceiling updates that go with them. holds the error
+    // of the last write if it failed
+    lock: Mutex<Option<io::Error>>,
+}
+
+impl HighWaterMark {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        HighWaterMark { path: path.into(), chunk: DEFAULT_CHUNK, lock: Mutex::new(None) }
+    }
+
+    /// How many IDs to reserve ahead with every write. Defaults to [`DEFAULT_CHUNK`].
+    pub fn with_chunk(mut self, chunk: UidTy) -> Self {
+        assert!(chunk > 0, "invalid high-water mark chunk");
+        self.chunk = chunk;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The mark currently on disk, `None` if the file doesn't exist yet.
+    pub fn load(&self) -> io::Result<Option<UidTy>> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => text
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid high-water mark")),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    // write to a temporary file and rename it over the old one, so a crash leaves either the old or the new mark
+    fn store(&self, mark: UidTy) -> io::Result<()> {
+        let mut tmp_name = self.path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp = self.path.with_file_name(tmp_name);
+
+        let mut file = File::create(&tmp)?;
+        writeln!(file, "{}", mark)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+
+        // the rename itself is only durable once the directory is synced
+        #[cfg(unix)]
+        {
+            let dir = match self.path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl UidGenerator {
+    /// Resumes above the mark recorded in `mark`'s file, and from now on keeps the file
+    /// ahead of every ID this generator hands out, so IDs stay unique across restarts and crashes.
+    ///
+    /// Call this before handing out IDs. If reading or writing the file fails, the
+    /// generator is left as it was and `persist` can be retried.
+    ///
+    /// If writing it fails later on, claims that need a new mark fail with [`UidExhausted`]
+    /// rather than hand out IDs that weren't recorded, whatever the [`Exhaustion`](crate::Exhaustion)
+    /// policy, and the next claim retries. The infallible methods panic with the I/O error,
+    /// [`take_persist_error`](Self::take_persist_error) returns it.
+    pub fn persist(&'static self, mark: HighWaterMark) -> io::Result<()> {
+        let already = || io::Error::new(io::ErrorKind::AlreadyExists, "generator is already persisted");
+        if self.mark.get().is_some() {
+            return Err(already());
+        }
+        let saved = mark.load()?.unwrap_or(0);
+        let next = self.counter().load(Ordering::Acquire).max(saved);
+        let new = next.saturating_add(mark.chunk).min(self.limit);
+        // nothing changes until the file is ahead of everything we'll hand out
+        mark.store(new)?;
+
+        let _ = self.counter().fetch_update(Ordering::AcqRel, |cur| (cur < saved).then_some(saved));
+        if self.mark.set(mark).is_err() {
+            return Err(already());
+        }
+        self.ceiling.store(new, Ordering::Release);
+        Ok(())
+    }
+
+    /// Why the high-water mark file couldn't be written, if the last write failed.
+    /// Cleared by taking it, or by the next write that succeeds.
+    pub fn take_persist_error(&self) -> Option<io::Error> {
+        let mark = self.mark.get()?;
+        mark.lock.lock().unwrap_or_else(|err| err.into_inner()).take()
+    }
+
+    // called before the infallible methods panic, so the panic names the real cause
+    pub(super) fn panic_if_unpersisted(&self) {
+        let Some(mark) = self.mark.get() else {
+            return;
+        };
+        let msg = match &*mark.lock.lock().unwrap_or_else(|err| err.into_inner()) {
+            Some(err) => format!("couldn't write the high-water mark: {}", err),
+            None => return,
+        };
+        panic!("{}", msg);
+    }
+
+    /// The mark last written to disk, `None` if the generator isn't persisted.
+    pub fn persisted_mark(&self) -> Option<UidTy> {
+        self.mark.get().map(|_| self.ceiling.load(Ordering::Acquire))
+    }
+
+    pub(super) fn ceiling(&self) -> UidTy {
+        self.ceiling.load(Ordering::Acquire)
+    }
+
+    // called when a claim would end above the ceiling
+    pub(super) fn raise_ceiling(&self, needed: UidTy) -> Result<(), UidExhausted> {
+        let Some(mark) = self.mark.get() else {
+            return Err(self.exhausted());
+        };
+        let mut error = mark.lock.lock().unwrap_or_else(|err| err.into_inner());
+        if needed <= self.ceiling.load(Ordering::Acquire) {
+            // someone else wrote the file in the meantime
+            return Ok(());
+        }
+        let new = needed.saturating_add(mark.chunk).min(self.limit).max(needed);
+        if let Err(err) = mark.store(new) {
+            // not the exhaustion policy's business, the next claim may well succeed
+            *error = Some(err);
+            return Err(UidExhausted);
+        }
+        *error = None;
+        self.ceiling.store(new, Ordering::Release);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HighWaterMark;
+    use crate::{BlockSize, Exhaustion, UidExhausted, UidGenerator, UidTy};
+    use std::{fs, io, path::PathBuf};
+
+    // an empty directory per test, removed again when the test ends, even by panicking
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("quid-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn join(&self, file: &str) -> PathBuf {
+            self.0.join(file)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_resumes_above_mark() {
+        static FIRST: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(4));
+        static SECOND: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(4));
+        let dir = TempDir::new("resume");
+        let path = dir.join("mark");
+
+        FIRST.persist(HighWaterMark::new(&path).with_chunk(10)).unwrap();
+        let first: Vec<UidTy> = (0..25).map(|_| FIRST.next_uid().into()).collect();
+        let on_disk = HighWaterMark::new(&path).load().unwrap().unwrap();
+        assert!(first.iter().all(|&id| id < on_disk));
+        assert_eq!(FIRST.persisted_mark(), Some(on_disk));
+        let err = FIRST.persist(HighWaterMark::new(&path)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+        // a restart, without the first generator handing anything back
+        SECOND.persist(HighWaterMark::new(&path).with_chunk(10)).unwrap();
+        let second: UidTy = SECOND.next_uid().into();
+        assert!(second >= on_disk);
+    }
+
+    #[test]
+    fn test_reserve_crosses_chunks() {
+        static GEN: UidGenerator = UidGenerator::new();
+        let dir = TempDir::new("reserve");
+        let path = dir.join("mark");
+
+        GEN.persist(HighWaterMark::new(&path).with_chunk(8)).unwrap();
+        let range = GEN.reserve(100);
+        assert!(range.end() <= HighWaterMark::new(&path).load().unwrap().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        static GEN: UidGenerator = UidGenerator::new();
+        let dir = TempDir::new("invalid");
+        let path = dir.join("mark");
+        fs::write(&path, "not a number").unwrap();
+
+        let err = GEN.persist(HighWaterMark::new(&path)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(GEN.persisted_mark(), None);
+        assert!(GEN.try_next_uid().is_ok());
+    }
+
+    #[test]
+    fn test_first_write_fails() {
+        static GEN: UidGenerator = UidGenerator::new();
+        let dir = TempDir::new("first-write");
+        fs::remove_dir(&dir.0).unwrap();
+
+        let err = GEN.persist(HighWaterMark::new(dir.join("mark"))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(GEN.persisted_mark(), None);
+        assert!(GEN.try_reserve(100).is_ok());
+
+        fs::create_dir_all(&dir.0).unwrap();
+        GEN.persist(HighWaterMark::new(dir.join("mark"))).unwrap();
+        assert!(GEN.persisted_mark().is_some());
+    }
+
+    #[test]
+    fn test_write_error() {
+        static GEN: UidGenerator = UidGenerator::new().with_exhaustion(Exhaustion::Abort);
+        let dir = TempDir::new("write-error");
+
+        GEN.persist(HighWaterMark::new(dir.join("mark")).with_chunk(8)).unwrap();
+        assert!(GEN.take_persist_error().is_none());
+        fs::remove_dir_all(&dir.0).unwrap();
+
+        // no abort, and the cause is kept
+        assert_eq!(GEN.try_reserve(100), Err(UidExhausted));
+        assert_eq!(GEN.take_persist_error().map(|err| err.kind()), Some(io::ErrorKind::NotFound));
+        assert!(GEN.take_persist_error().is_none());
+
+        fs::create_dir_all(&dir.0).unwrap();
+        assert!(GEN.try_reserve(100).is_ok());
+        assert!(GEN.take_persist_error().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "couldn't write the high-water mark")]
+    fn test_write_error_panic() {
+        static GEN: UidGenerator = UidGenerator::new();
+        let dir = TempDir::new("write-error-panic");
+
+        GEN.persist(HighWaterMark::new(dir.join("mark")).with_chunk(8)).unwrap();
+        fs::remove_dir_all(&dir.0).unwrap();
+        GEN.reserve(100);
+    }
+}
@@ -0,0 +1,153 @@
+use core::{ptr, sync::atomic::Ordering};
+use std::{ffi::CString, io};
+
+use super::UidGenerator;
+use crate::{counter::AtomicUid, UidTy};
+
+// one page, the counter sits at the start
+const SEGMENT_LEN: usize = 4096;
+
+fn segment_name(name: &str) -> io::Result<CString> {
+    let name = if name.starts_with('/') { name.to_owned() } else { format!("/{}", name) };
+    if name[1..].contains('/') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "shared memory names can't contain '/'"));
+    }
+    CString::new(name).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "shared memory name contains a NUL byte"))
+}
+
+fn cvt(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+// maps the segment for the rest of the process's life
+fn map_segment(name: &CString) -> io::Result<&'static AtomicUid> {
+    unsafe {
+        let fd = cvt(libc::shm_open(name.as_ptr(), libc::O_RDWR | libc::O_CREAT, 0o600))?;
+        // every process truncates to the same length, so this never cuts a counter off
+        let mapped = cvt(libc::ftruncate(fd, SEGMENT_LEN as libc::off_t)).map(|_| {
+            libc::mmap(ptr::null_mut(), SEGMENT_LEN, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, fd, 0)
+        });
+        libc::close(fd);
+        let addr = mapped?;
+        if addr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // page aligned, zeroed when the segment is created, and never unmapped
+        Ok(AtomicUid::from_ptr(addr.cast::<UidTy>()))
+    }
+}
+
+impl UidGenerator {
+    /// Moves the generator's counter into the shared memory segment `name` (in `/dev/shm`
+    /// on Linux), creating it if needed, so every process attached to the same name
+    /// hands out different IDs. Threads still reserve blocks from the shared counter
+    /// and hand out IDs from them locally.
+    ///
+    /// Attach before handing out any IDs, and after forking: blocks a thread has
+    /// cached are copied into the child by `fork`.
+    pub fn attach_shared(&'static self, name: &str) -> io::Result<()> {
+        let already = || io::Error::new(io::ErrorKind::AlreadyExists, "generator is already attached");
+        if self.shared.get().is_some() {
+            return Err(already());
+        }
+        if self.next.load(Ordering::Relaxed) != 1 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "generator has already handed out IDs"));
+        }
+        let counter = map_segment(&segment_name(name)?)?;
+        // a fresh segment is zeroed, and 0 is never handed out
+        let _ = counter.fetch_update(Ordering::Relaxed, |cur| (cur == 0).then_some(1));
+        self.shared.set(counter).map_err(|_| already())
+    }
+
+    /// Removes the segment `name`. Processes that are attached keep using it, later
+    /// attaches create a new one starting from 1 again.
+    pub fn unlink_shared(name: &str) -> io::Result<()> {
+        let name = segment_name(name)?;
+        cvt(unsafe { libc::shm_unlink(name.as_ptr()) }).map(drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BlockSize, UidGenerator, UidTy};
+    use std::collections::HashSet;
+    use std::process::{Command, Stdio};
+    use std::{env, fs, io};
+
+    const CHILDREN: usize = 4;
+    const IDS_PER_CHILD: usize = 20_000;
+    // set in the children, the test binary re-run on just the test below
+    const CHILD_ENV: &str = "QUID_SHM_TEST_CHILD";
+
+    fn test_name(name: &str) -> String {
+        format!("quid-test-{}-{}", std::process::id(), name)
+    }
+
+    #[test]
+    fn test_processes_share_counter() {
+        static GEN: UidGenerator = UidGenerator::new().with_block_size(BlockSize::Fixed(64));
+
+        // in a child: hand out IDs from the segment and write them to the given file
+        if let Ok(child) = env::var(CHILD_ENV) {
+            let (name, out) = child.split_once(':').unwrap();
+            GEN.attach_shared(name).unwrap();
+            let ids: Vec<u8> = (0..IDS_PER_CHILD)
+                .flat_map(|_| UidTy::from(GEN.next_uid()).to_ne_bytes())
+                .collect();
+            fs::write(out, ids).unwrap();
+            return;
+        }
+
+        let name = test_name("children");
+        let dir = env::temp_dir().join(&name);
+        fs::create_dir_all(&dir).unwrap();
+        let children: Vec<_> = (0..CHILDREN)
+            .map(|i| {
+                let out = dir.join(i.to_string());
+                let child = Command::new(env::current_exe().unwrap())
+                    .args(["--exact", "generator::shared::tests::test_processes_share_counter", "--quiet"])
+                    .env(CHILD_ENV, format!("{}:{}", name, out.display()))
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .unwrap();
+                (child, out)
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for (mut child, out) in children {
+            assert!(child.wait().unwrap().success(), "child failed");
+            let bytes = fs::read(out).unwrap();
+            assert_eq!(bytes.len(), IDS_PER_CHILD * size_of::<UidTy>());
+            for chunk in bytes.chunks_exact(size_of::<UidTy>()) {
+                let id = UidTy::from_ne_bytes(chunk.try_into().unwrap());
+                assert!(seen.insert(id), "duplicate ID {} across processes", id);
+            }
+        }
+        let _ = fs::remove_dir_all(&dir);
+
+        // the parent attaches last and carries on above everything the children reserved
+        GEN.attach_shared(&name).unwrap();
+        assert!(GEN.high_water_mark() > *seen.iter().max().unwrap());
+        UidGenerator::unlink_shared(&name).unwrap();
+    }
+
+    #[test]
+    fn test_attach_errors() {
+        static USED: UidGenerator = UidGenerator::new();
+        static GEN: UidGenerator = UidGenerator::new();
+        let name = test_name("errors");
+
+        USED.next_uid();
+        assert_eq!(USED.attach_shared(&name).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(GEN.attach_shared("a/b").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        GEN.attach_shared(&name).unwrap();
+        assert_eq!(GEN.attach_shared(&name).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        UidGenerator::unlink_shared(&name).unwrap();
+    }
+}
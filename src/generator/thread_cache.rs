@@ -0,0 +1,132 @@
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::{Block, UidGenerator};
+
+static NEXT_SLOT: AtomicUsize = AtomicUsize::new(0);
+
+// cache slot index + 1, 0 until the generator is first used. every generator gets its
+// own slot, so generators never evict each other's cached state
+pub(crate) struct SlotIndex(AtomicUsize);
+
+/// What a fresh slot of a [`SlotCache`] holds.
+pub(crate) trait SlotEntry: Copy {
+    const EMPTY: Self;
+}
+
+// a thread's cached state for each generator, grown as generators are first used on the thread
+pub(crate) struct SlotCache<T>(RefCell<Vec<T>>);
+
+impl<T: SlotEntry> SlotCache<T> {
+    pub(crate) const fn new() -> Self {
+        SlotCache(RefCell::new(Vec::new()))
+    }
+
+    pub(crate) fn get(&self, idx: usize) -> T {
+        self.0.borrow().get(idx).copied().unwrap_or(T::EMPTY)
+    }
+
+    pub(crate) fn set(&self, idx: usize, value: T) {
+        let mut entries = self.0.borrow_mut();
+        if entries.len() <= idx {
+            entries.resize(idx + 1, T::EMPTY);
+        }
+        entries[idx] = value;
+    }
+
+    fn take_all(&self) -> Vec<T> {
+        self.0.take()
+    }
+}
+
+impl SlotEntry for Block {
+    const EMPTY: Block = Block::EMPTY;
+}
+
+impl SlotIndex {
+    pub(crate) const fn new() -> Self {
+        SlotIndex(AtomicUsize::new(0))
+    }
+
+    pub(crate) fn get(&self) -> usize {
+        let mut slot = self.0.load(Ordering::Relaxed);
+        if slot == 0 {
+            let fresh = NEXT_SLOT.fetch_add(1, Ordering::Relaxed) + 1;
+            slot = match self.0.compare_exchange(0, fresh, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => fresh,
+                Err(cur) => cur,
+            };
+        }
+        slot - 1
+    }
+}
+
+#[cfg(not(feature = "stable"))]
+#[thread_local]
+static CACHE: SlotCache<Block> = SlotCache::new();
+
+// `#[thread_local]` statics have no destructors, so a regular thread local flushes them on thread exit
+#[cfg(not(feature = "stable"))]
+struct CacheGuard;
+
+#[cfg(not(feature = "stable"))]
+impl Drop for CacheGuard {
+    fn drop(&mut self) {
+        for block in CACHE.take_all() {
+            block.give_back();
+        }
+    }
+}
+
+#[cfg(not(feature = "stable"))]
+std::thread_local! {
+    static CACHE_GUARD: CacheGuard = const { CacheGuard };
+}
+
+#[cfg(feature = "stable")]
+struct Cache(SlotCache<Block>);
+
+#[cfg(feature = "stable")]
+impl Drop for Cache {
+    fn drop(&mut self) {
+        for block in self.0.take_all() {
+            block.give_back();
+        }
+    }
+}
+
+#[cfg(feature = "stable")]
+std::thread_local! {
+    static CACHE: Cache = const { Cache(SlotCache::new()) };
+}
+
+// `None` once the thread's cache has been torn down
+#[cfg(not(feature = "stable"))]
+fn with_cache<R>(f: impl FnOnce(&SlotCache<Block>) -> R) -> Option<R> {
+    Some(f(&CACHE))
+}
+
+#[cfg(feature = "stable")]
+fn with_cache<R>(f: impl FnOnce(&SlotCache<Block>) -> R) -> Option<R> {
+    CACHE.try_with(|cache| f(&cache.0)).ok()
+}
+
+pub(super) fn register_dtor() {
+    #[cfg(not(feature = "stable"))]
+    let _ = CACHE_GUARD.try_with(|_| {});
+}
+
+impl UidGenerator {
+    pub(super) fn with_thread_block<R>(&'static self, f: impl FnOnce(&mut Block) -> R) -> Option<R> {
+        let idx = self.slot.get();
+        with_cache(|cache| {
+            // not borrowed while `f` runs, which may panic
+            let mut block = cache.get(idx);
+            let res = f(&mut block);
+            cache.set(idx, block);
+            res
+        })
+    }
+}
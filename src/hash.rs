@@ -0,0 +1,110 @@
+use core::hash::{BuildHasher, Hasher};
+
+#[cfg(feature = "std")]
+use crate::UID;
+
+// 2^64 / golden ratio, odd so the multiplication loses nothing
+const MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A [`HashMap`](std::collections::HashMap) keyed by [`UID`], hashed with [`UidBuildHasher`].
+#[cfg(feature = "std")]
+pub type UidMap<V> = std::collections::HashMap<UID, V, UidBuildHasher>;
+
+/// A [`HashSet`](std::collections::HashSet) of [`UID`]s, hashed with [`UidBuildHasher`].
+#[cfg(feature = "std")]
+pub type UidSet = std::collections::HashSet<UID, UidBuildHasher>;
+
+/// Builds [`UidHasher`]s, for hash maps and sets keyed by IDs.
+///
+/// IDs are unique already, so hashing them only needs to spread their bits, not
+/// resist collisions chosen by an attacker. Keys parsed from untrusted input are
+/// better off with std's default hasher.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UidBuildHasher;
+
+impl BuildHasher for UidBuildHasher {
+    type Hasher = UidHasher;
+
+    fn build_hasher(&self) -> UidHasher {
+        UidHasher::default()
+    }
+}
+
+/// One multiplication per word, with the high half of the product folded into the low
+/// one, so the top bits hash tables look at depend on every bit of the ID.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UidHasher {
+    hash: u64,
+}
+
+impl UidHasher {
+    fn add(&mut self, word: u64) {
+        let product = u128::from(self.hash ^ word) * u128::from(MULTIPLIER);
+        self.hash = (product as u64) ^ ((product >> 64) as u64);
+    }
+}
+
+impl Hasher for UidHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, n: u8) {
+        self.add(n.into())
+    }
+
+    fn write_u16(&mut self, n: u16) {
+        self.add(n.into())
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.add(n.into())
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.add(n)
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64)
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UidBuildHasher, UidMap, UidSet};
+    use crate::{UidTy, UID};
+    use core::hash::BuildHasher;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_map_and_set() {
+        let mut map = UidMap::default();
+        let mut set = UidSet::default();
+        for _ in 0..1000 {
+            let uid = UID::new();
+            map.insert(uid, UidTy::from(uid));
+            assert!(set.insert(uid));
+        }
+        assert_eq!(map.len(), 1000);
+        assert!(map.iter().all(|(uid, &raw)| UidTy::from(*uid) == raw && set.contains(uid)));
+    }
+
+    #[test]
+    fn test_spreads_top_bits() {
+        // hashbrown picks buckets with the low bits and filters with the top 7
+        let top: HashSet<u64> = (1..=512).map(|n| UidBuildHasher.hash_one(UID(n)) >> 57).collect();
+        let low: HashSet<u64> = (1..=512).map(|n| UidBuildHasher.hash_one(UID(n)) & 0x1ff).collect();
+        assert_eq!(top.len(), 128);
+        assert!(low.len() > 256, "only {} distinct buckets", low.len());
+        assert_ne!(UidBuildHasher.hash_one(UID(1)), UidBuildHasher.hash_one(UID(2)));
+    }
+}
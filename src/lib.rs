@@ -1,66 +1,125 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-#![cfg_attr(not(feature = "stable"), feature(thread_local))]
+#![cfg_attr(all(feature = "std", not(feature = "stable")), feature(thread_local))]
 
-use core::{cell::Cell, sync::atomic::{AtomicU64, Ordering}};
+#[cfg(feature = "std")]
+mod clock;
+mod counter;
+mod encoding;
+mod error;
+#[cfg(feature = "fmt")]
+mod format;
+mod free_list;
+mod generator;
+mod hash;
+#[cfg(feature = "net")]
+pub mod net;
+mod range;
+mod repr;
+#[cfg(feature = "serde")]
+pub mod serde;
+// the default layout needs 64 bits
+#[cfg(all(feature = "std", not(quid_uid32)))]
+mod snowflake;
+mod tagged;
+mod ulid;
+mod uuid;
 
 #[cfg(feature = "std")]
-use nostd::fmt;
+pub use clock::{Clock, ClockRegression, SystemClock};
+pub use encoding::Encoding;
+pub use error::{ClockError, ParseUidError, UidExhausted};
+#[cfg(feature = "fmt")]
+pub use format::{Base32, Base62};
+pub use generator::{BlockSize, Exhaustion, Monotonicity, UidCache, UidGenerator, DEFAULT_BLOCK_SIZE};
+#[cfg(feature = "std")]
+pub use generator::{HighWaterMark, DEFAULT_CHUNK};
+pub use hash::{UidBuildHasher, UidHasher};
+#[cfg(feature = "std")]
+pub use hash::{UidMap, UidSet};
+pub use range::UidRange;
+pub use repr::UidRepr;
+#[cfg(all(feature = "std", not(quid_uid32)))]
+pub use snowflake::{Layout, Snowflake, SnowflakeGenerator, DEFAULT_EPOCH};
+pub use tagged::{NonZeroUid32, NonZeroUid64, Uid, Uid128, Uid32, Uid64, UidTag};
+pub use ulid::Ulid;
+#[cfg(feature = "std")]
+pub use ulid::UlidGenerator;
+pub use uuid::Uuid;
+#[cfg(feature = "std")]
+pub use uuid::{UuidV7Generator, UuidV8Generator};
 
+#[cfg(not(quid_uid32))]
 pub type UidTy = u64;
+#[cfg(quid_uid32)]
+pub type UidTy = u32;
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+/// Ordered by value. Each thread hands out IDs from its own block, so across threads
+/// that isn't the order they were handed out in.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct UID(UidTy);
 
-#[cfg(feature = "std")]
-impl fmt::Debug for UID {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "UID({})", self.0)
+impl From<UID> for UidTy {
+    fn from(uid: UID) -> UidTy {
+        uid.0
     }
 }
 
-#[cfg(feature = "std")]
-impl fmt::Display for UID {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self)
+#[cfg(quid_uid32)]
+impl From<UID> for u64 {
+    fn from(uid: UID) -> u64 {
+        uid.0.into()
     }
 }
 
-impl Into<UidTy> for UID {
-    fn into(self) -> UidTy {
-        self.0
+static GLOBAL_NEXT_UID: UidGenerator = UidGenerator::new();
+
+impl UID {
+    // no Default: a fresh ID as the default value would be surprising, and a fixed one
+    // would look like a real ID
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        GLOBAL_NEXT_UID.next_uid()
     }
-}
 
-static GLOBAL_NEXT_UID: AtomicU64 = AtomicU64::new(0);
+    pub fn try_new() -> Result<Self, UidExhausted> {
+        GLOBAL_NEXT_UID.try_next_uid()
+    }
 
-#[cfg(not(feature = "stable"))]
-#[thread_local]
-static UID_BASE: Cell<UidTy> = const { Cell::new(0) };
+    pub fn new_in(cache: &mut UidCache) -> Self {
+        GLOBAL_NEXT_UID.next_uid_in(cache)
+    }
 
-#[cfg(not(feature = "stable"))]
-#[thread_local]
-static UID_REM: Cell<UidTy> = const { Cell::new(0) };
+    pub fn try_new_in(cache: &mut UidCache) -> Result<Self, UidExhausted> {
+        GLOBAL_NEXT_UID.try_next_uid_in(cache)
+    }
 
-#[cfg(feature = "stable")]
-std::thread_local! {
-    static UID_BASE: Cell<UidTy> = const { Cell::new(0) };
-    static UID_REM: Cell<UidTy> = const { Cell::new(0) };
-}
+    pub fn reserve(n: UidTy) -> UidRange {
+        GLOBAL_NEXT_UID.reserve(n)
+    }
 
-static TH_ALLOC_STEP: UidTy = 512;
+    pub fn try_reserve(n: UidTy) -> Result<UidRange, UidExhausted> {
+        GLOBAL_NEXT_UID.try_reserve(n)
+    }
 
-impl UID {
-    pub fn new() -> Self {
-        if UID_REM.get() == 0 {
-            let base = GLOBAL_NEXT_UID.fetch_add(TH_ALLOC_STEP, Ordering::Relaxed);
-            UID_BASE.set(base);
-            UID_REM.set(TH_ALLOC_STEP - 1);
-            UID(base + TH_ALLOC_STEP - 1)
-        } else {
-            let val = UID_REM.get() - 1;
-            UID_REM.set(val);
-            UID(UID_BASE.get() + val)
-        }
+    /// Makes sure the IDs reserved from now on are at least `min`, see [`UidGenerator::resume_from`].
+    pub fn resume_from(min: UidTy) {
+        GLOBAL_NEXT_UID.resume_from(min)
+    }
+
+    pub fn high_water_mark() -> UidTy {
+        GLOBAL_NEXT_UID.high_water_mark()
+    }
+
+    /// What `UID::new()` and friends do once the global generator runs out, see [`Exhaustion`].
+    /// Defaults to [`Exhaustion::Error`].
+    pub fn set_exhaustion(policy: Exhaustion) {
+        GLOBAL_NEXT_UID.set_exhaustion(policy)
+    }
+
+    /// Persists the global generator, see [`UidGenerator::persist`].
+    #[cfg(feature = "std")]
+    pub fn persist(mark: HighWaterMark) -> std::io::Result<()> {
+        GLOBAL_NEXT_UID.persist(mark)
     }
 }
 
@@ -79,6 +138,38 @@ mod tests {
         assert_ne!(uid1, uid2, "UIDs should be unique");
     }
 
+    #[test]
+    fn test_try_new() {
+        let uid = UID::new();
+        assert_ne!(UID::try_new().unwrap(), uid);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let a = UID(3);
+        let b = a;
+        assert_eq!(a, b);
+        assert!(UID(2) < a);
+
+        let mut ids = vec![UID(5), UID(1), UID(3)];
+        ids.sort();
+        assert_eq!(ids, [UID(1), UID(3), UID(5)]);
+
+        let names: std::collections::BTreeMap<UID, &str> = [(UID(2), "b"), (UID(1), "a")].into();
+        assert_eq!(names.keys().copied().map(u64::from).collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn test_resume_global() {
+        let min = UID::high_water_mark() + 1000;
+        UID::resume_from(min);
+        UID::resume_from(min - 10);
+        assert!(UID::high_water_mark() >= min);
+        // a fresh thread has no block to take the range from
+        let range = std::thread::spawn(|| UID::reserve(1)).join().unwrap();
+        assert!(range.start() >= min);
+    }
+
     #[test]
     fn test_uid_concurrency_safety() {
         const THREADS: usize = 10;
@@ -99,7 +190,7 @@ mod tests {
                 let mut shared = ids_clone.lock().unwrap();
                 for id in local_ids {
                     assert!(
-                        shared.insert(id.clone()),
+                        shared.insert(id),
                         "Duplicate UID found: {}",
                         id
                     );
@@ -0,0 +1,368 @@
+//! Leasing blocks of IDs over TCP, so several hosts can share one [`UidGenerator`].
+//!
+//! Every message is a big-endian `u32` length followed by that many bytes. A request
+//! is the byte `1` and the number of IDs wanted as a big-endian `u64`. The reply is a
+//! status byte: `0` followed by the start and end of the leased range as big-endian
+//! `u64`s, `1` if the server's generator is exhausted, or `2` for a malformed request,
+//! after which the server closes the connection.
+
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{UidExhausted, UidGenerator, UidRange, UidTy, UID};
+
+pub const DEFAULT_PORT: u16 = 7420;
+/// The largest lease the server hands out, bigger requests get this many IDs.
+pub const MAX_LEASE: u64 = 1 << 24;
+
+const LEASE: u8 = 1;
+const OK: u8 = 0;
+const EXHAUSTED: u8 = 1;
+const BAD_REQUEST: u8 = 2;
+// nothing in the protocol is bigger
+const MAX_FRAME: u32 = 64;
+// how long the server waits for a client's next request before hanging up
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn read_frame(r: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    r.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too long"));
+    }
+    let mut buf = vec![0; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn write_frame(w: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    w.write_all(&frame)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Accepts connections forever, answering lease requests from `gen` on a thread per connection.
+/// Connections idle for a minute are closed.
+///
+/// Failed accepts are passed to `on_error` and retried. This only returns if the listener
+/// itself is unusable.
+pub fn serve(listener: TcpListener, gen: &'static UidGenerator, mut on_error: impl FnMut(io::Error)) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) if matches!(err.kind(), io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported) => {
+                return Err(err)
+            }
+            Err(err) => {
+                // out of file descriptors or memory, give connections time to close
+                let transient = matches!(
+                    err.kind(),
+                    io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted
+                );
+                on_error(err);
+                if !transient {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                continue;
+            }
+        };
+        thread::spawn(move || {
+            // a client hanging up is nothing the server can do anything about
+            let _ = handle(stream, gen);
+        });
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, gen: &'static UidGenerator) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    while answer(&mut stream, gen)? {}
+    Ok(())
+}
+
+// answers one request, false once the connection is done with
+#[allow(clippy::useless_conversion)]
+fn answer(stream: &mut TcpStream, gen: &'static UidGenerator) -> io::Result<bool> {
+    let request = match read_frame(stream) {
+        Ok(request) => request,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    let count = match request[..] {
+        [LEASE, ref count @ ..] if count.len() == 8 => u64::from_be_bytes(count.try_into().unwrap()),
+        _ => return write_frame(stream, &[BAD_REQUEST]).map(|_| false),
+    };
+    let count = UidTy::try_from(count.clamp(1, MAX_LEASE)).unwrap_or(UidTy::MAX);
+
+    let mut reply = Vec::with_capacity(17);
+    match gen.try_reserve(count) {
+        Ok(range) => {
+            reply.push(OK);
+            reply.extend_from_slice(&u64::from(range.start()).to_be_bytes());
+            reply.extend_from_slice(&u64::from(range.end()).to_be_bytes());
+        }
+        Err(UidExhausted) => reply.push(EXHAUSTED),
+    }
+    write_frame(stream, &reply)?;
+    Ok(true)
+}
+
+// the outer error is the connection's, the inner one the server's answer.
+// UidTy is u32 with quid_uid32
+#[allow(clippy::useless_conversion)]
+fn request_lease(stream: &mut TcpStream, count: UidTy) -> io::Result<Result<UidRange, UidExhausted>> {
+    let mut request = vec![LEASE];
+    request.extend_from_slice(&u64::from(count).to_be_bytes());
+    write_frame(stream, &request)?;
+
+    let reply = read_frame(stream)?;
+    match reply[..] {
+        [OK, ref range @ ..] if range.len() == 16 => {
+            let start = u64::from_be_bytes(range[..8].try_into().unwrap());
+            let end = u64::from_be_bytes(range[8..].try_into().unwrap());
+            match (UidTy::try_from(start), UidTy::try_from(end)) {
+                (Ok(start), Ok(end)) if start < end => Ok(Ok(UidRange::new(start, end))),
+                _ => Err(invalid("invalid lease")),
+            }
+        }
+        [EXHAUSTED] => Ok(Err(UidExhausted)),
+        _ => Err(invalid("unexpected reply")),
+    }
+}
+
+/// Hands out IDs leased from a [`serve`] instance in blocks.
+///
+/// A background thread leases the next block once half of the current one is used,
+/// so callers only wait for the network when IDs are taken faster than it can keep up.
+pub struct RemoteGenerator {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    // what the address passed to `connect` resolved to, for reconnecting
+    addrs: Vec<SocketAddr>,
+    state: Mutex<State>,
+    changed: Condvar,
+    lease: UidTy,
+}
+
+struct State {
+    current: UidRange,
+    prefetched: Option<UidRange>,
+    // the last lease failed, handed to the next caller that runs out
+    error: Option<io::Error>,
+    closed: bool,
+}
+
+impl State {
+    fn wants_lease(&self, lease: UidTy) -> bool {
+        self.prefetched.is_none() && self.error.is_none() && self.current.end() - self.current.start() <= lease / 2
+    }
+}
+
+impl RemoteGenerator {
+    /// Connects to the server and leases blocks of `lease` IDs at a time.
+    pub fn connect(addr: impl ToSocketAddrs, lease: UidTy) -> io::Result<Self> {
+        assert!(lease > 0, "invalid lease size");
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        let mut stream = TcpStream::connect(&addrs[..])?;
+        stream.set_nodelay(true)?;
+        let current = request_lease(&mut stream, lease)?.map_err(io::Error::other)?;
+
+        let shared = Arc::new(Shared {
+            addrs,
+            state: Mutex::new(State { current, prefetched: None, error: None, closed: false }),
+            changed: Condvar::new(),
+            lease,
+        });
+        let fetcher = Arc::clone(&shared);
+        thread::spawn(move || fetcher.prefetch(stream));
+        Ok(RemoteGenerator { shared })
+    }
+
+    pub fn next_uid(&self) -> UID {
+        match self.try_next_uid() {
+            Ok(uid) => uid,
+            Err(err) => panic!("couldn't lease UIDs: {}", err),
+        }
+    }
+
+    /// Waits for the next lease if the current one is used up. A failed lease is
+    /// returned to one caller, the next call tries again.
+    pub fn try_next_uid(&self) -> io::Result<UID> {
+        let shared = &*self.shared;
+        let mut state = shared.state.lock().unwrap_or_else(|err| err.into_inner());
+        loop {
+            if let Some(uid) = state.current.next() {
+                if state.wants_lease(shared.lease) {
+                    shared.changed.notify_all();
+                }
+                return Ok(uid);
+            }
+            if let Some(next) = state.prefetched.take() {
+                state.current = next;
+                continue;
+            }
+            if let Some(err) = state.error.take() {
+                shared.changed.notify_all();
+                return Err(err);
+            }
+            state = shared.changed.wait(state).unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+impl Shared {
+    fn prefetch(&self, mut stream: TcpStream) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        loop {
+            while !state.closed && !state.wants_lease(self.lease) {
+                state = self.changed.wait(state).unwrap_or_else(|err| err.into_inner());
+            }
+            if state.closed {
+                return;
+            }
+            drop(state);
+
+            let res = request_lease(&mut stream, self.lease).or_else(|_| {
+                // the server may have restarted or hung up on an idle connection,
+                // try once more on a fresh one
+                stream = TcpStream::connect(&self.addrs[..])?;
+                stream.set_nodelay(true)?;
+                request_lease(&mut stream, self.lease)
+            });
+
+            state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+            match res {
+                Ok(Ok(range)) => state.prefetched = Some(range),
+                Ok(Err(err)) => state.error = Some(io::Error::other(err)),
+                Err(err) => state.error = Some(err),
+            }
+            self.changed.notify_all();
+        }
+    }
+}
+
+impl Drop for RemoteGenerator {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{answer, serve, RemoteGenerator};
+    use crate::{UidExhausted, UidGenerator, UidTy};
+    use std::collections::HashSet;
+    use std::io;
+    use std::net::{SocketAddr, TcpListener};
+    use std::thread;
+
+    fn spawn_server(gen: &'static UidGenerator) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, gen, drop));
+        addr
+    }
+
+    #[test]
+    fn test_clients_get_disjoint_leases() {
+        static GEN: UidGenerator = UidGenerator::new();
+        let addr = spawn_server(&GEN);
+
+        let threads: Vec<_> = (0..3)
+            .map(|_| {
+                thread::spawn(move || {
+                    let client = RemoteGenerator::connect(addr, 16).unwrap();
+                    let ids: Vec<UidTy> = (0..1000).map(|_| client.next_uid().into()).collect();
+                    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+                    ids
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for t in threads {
+            for id in t.join().unwrap() {
+                assert!(seen.insert(id), "duplicate ID {}", id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reconnects_after_restart() {
+        static GEN: UidGenerator = UidGenerator::new();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // the restarted server is listening before the old connection goes away
+            drop(listener);
+            let restarted = TcpListener::bind(addr).unwrap();
+            thread::spawn(move || serve(restarted, &GEN, drop));
+            // the first lease and the one prefetched after it
+            for _ in 0..2 {
+                assert!(answer(&mut stream, &GEN).unwrap());
+            }
+        });
+
+        let client = RemoteGenerator::connect(addr, 10).unwrap();
+        let ids: Vec<UidTy> = (0..100).map(|_| client.next_uid().into()).collect();
+        server.join().unwrap();
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+    }
+
+    #[test]
+    fn test_exhausted_server() {
+        static GEN: UidGenerator = UidGenerator::new().with_limit(21);
+        let addr = spawn_server(&GEN);
+
+        let client = RemoteGenerator::connect(addr, 10).unwrap();
+        for _ in 0..20 {
+            client.next_uid();
+        }
+        let err = client.try_next_uid().unwrap_err();
+        assert_eq!(err.get_ref().and_then(|err| err.downcast_ref()), Some(&UidExhausted));
+    }
+
+    #[test]
+    fn test_exhausted_reply_keeps_connection() {
+        static GEN: UidGenerator = UidGenerator::new().with_limit(11);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepting = listener.try_clone().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = accepting.accept().unwrap();
+            // the first lease and the exhausted prefetch
+            for _ in 0..2 {
+                assert!(answer(&mut stream, &GEN).unwrap());
+            }
+            stream
+        });
+
+        let client = RemoteGenerator::connect(addr, 10).unwrap();
+        for _ in 0..10 {
+            client.next_uid();
+        }
+        assert!(client.try_next_uid().is_err());
+        let _stream = server.join().unwrap();
+        listener.set_nonblocking(true).unwrap();
+        assert_eq!(listener.accept().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    }
+}
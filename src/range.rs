@@ -0,0 +1,131 @@
+use core::{iter::FusedIterator, ops::Range};
+
+use crate::{UidTy, UID};
+
+/// A contiguous, half-open range of IDs claimed with [`UID::reserve`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UidRange {
+    start: UidTy,
+    end: UidTy,
+}
+
+impl UidRange {
+    pub(crate) const fn new(start: UidTy, end: UidTy) -> Self {
+        UidRange { start, end }
+    }
+
+    pub const fn start(&self) -> UidTy {
+        self.start
+    }
+
+    pub const fn end(&self) -> UidTy {
+        self.end
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn contains(&self, uid: &UID) -> bool {
+        (self.start..self.end).contains(&uid.0)
+    }
+
+    /// Splits into `[0, mid)` and `[mid, len)`. Panics if `mid` is out of bounds.
+    pub fn split_at(&self, mid: UidTy) -> (UidRange, UidRange) {
+        assert!(mid <= self.end - self.start, "split index out of range");
+        let mid = self.start + mid;
+        (UidRange::new(self.start, mid), UidRange::new(mid, self.end))
+    }
+
+    /// Takes the sub-range at the given offsets. Panics if it is out of bounds.
+    pub fn slice(&self, range: Range<UidTy>) -> UidRange {
+        assert!(
+            range.start <= range.end && range.end <= self.end - self.start,
+            "slice index out of range"
+        );
+        UidRange::new(self.start + range.start, self.start + range.end)
+    }
+}
+
+impl Iterator for UidRange {
+    type Item = UID;
+
+    fn next(&mut self) -> Option<UID> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        Some(UID(self.start - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::try_from(self.end - self.start).unwrap_or(usize::MAX);
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<UID> {
+        let skip = UidTy::try_from(n).unwrap_or(UidTy::MAX).min(self.end - self.start);
+        self.start += skip;
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for UidRange {
+    fn next_back(&mut self) -> Option<UID> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(UID(self.end))
+    }
+}
+
+impl ExactSizeIterator for UidRange {}
+
+impl FusedIterator for UidRange {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{UidTy, UID};
+
+    #[test]
+    fn test_reserved_range_is_unique() {
+        let range = UID::reserve(1000);
+        assert_eq!(range.len(), 1000);
+
+        let single = UID::new();
+        assert!(!range.contains(&single));
+
+        let ids: Vec<UidTy> = range.clone().map(UidTy::from).collect();
+        assert!(ids.windows(2).all(|w| w[1] == w[0] + 1));
+        assert!(range.clone().all(|uid| range.contains(&uid)));
+    }
+
+    #[test]
+    fn test_range_split_and_slice() {
+        let range = UID::reserve(10);
+        let start = range.start();
+
+        let (left, right) = range.split_at(4);
+        assert_eq!((left.start(), left.end()), (start, start + 4));
+        assert_eq!((right.start(), right.end()), (start + 4, start + 10));
+
+        let mid = range.slice(2..5);
+        assert_eq!(mid.len(), 3);
+        assert_eq!(mid.clone().next_back().map(UidTy::from), Some(start + 4));
+        assert_eq!(mid.rev().map(UidTy::from).collect::<Vec<_>>(), [start + 4, start + 3, start + 2]);
+    }
+
+    #[test]
+    fn test_nth() {
+        let mut range = UID::reserve(10);
+        let start = range.start();
+        assert_eq!(range.nth(3).map(UidTy::from), Some(start + 3));
+        assert_eq!(range.len(), 6);
+        assert_eq!(range.nth(6), None);
+
+        // more than a 32-bit UidTy holds, which mustn't wrap around to a small skip
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(UID::reserve(10).nth((1 << 32) | 1), None);
+    }
+}
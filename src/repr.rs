@@ -0,0 +1,50 @@
+use core::{fmt, hash::Hash, num::{NonZeroU32, NonZeroU64}};
+
+use crate::UidTy;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An integer type a [`crate::Uid`] can be stored as.
+///
+/// Generators never hand out 0, so the `NonZero` types can hold every ID and
+/// give `Option<Uid<T, NonZeroU64>>` the same size as the ID itself.
+///
+/// ```
+/// use quid::{NonZeroUid64, Uid32};
+///
+/// let small = Uid32::new();
+/// let sentinel: Option<NonZeroUid64> = None;
+/// assert_eq!(core::mem::size_of_val(&sentinel), 8);
+/// ```
+pub trait UidRepr:
+    sealed::Sealed + Copy + Eq + Ord + Hash + fmt::Debug + fmt::Display + fmt::LowerHex + fmt::UpperHex + 'static
+{
+    /// `None` if the counter value doesn't fit.
+    fn from_uid_ty(raw: UidTy) -> Option<Self>;
+}
+
+macro_rules! impl_repr {
+    ($($ty:ty => |$raw:ident| $conv:expr;)*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl UidRepr for $ty {
+                // some of these are no-ops for one of the `UidTy` widths
+                #[allow(clippy::useless_conversion)]
+                fn from_uid_ty($raw: UidTy) -> Option<Self> {
+                    $conv
+                }
+            }
+        )*
+    };
+}
+
+impl_repr! {
+    u32 => |raw| raw.try_into().ok();
+    u64 => |raw| Some(raw.into());
+    u128 => |raw| Some(raw.into());
+    NonZeroU32 => |raw| NonZeroU32::new(raw.try_into().ok()?);
+    NonZeroU64 => |raw| NonZeroU64::new(raw.into());
+}
@@ -0,0 +1,157 @@
+//! `UID` serialises as a number in binary formats and as a decimal string in
+//! human-readable ones, since JSON numbers lose precision past 2^53 in JavaScript.
+//! Either form is accepted when deserialising from a human-readable format.
+//!
+//! Use [`as_number`] or [`as_string`] with `#[serde(with = "...")]` to pick one.
+
+use core::fmt;
+
+use ::serde::{
+    de::{self, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    encoding::{encode, DECIMAL},
+    UidTy, UID,
+};
+
+// enough for u64::MAX
+const DECIMAL_LEN: usize = 20;
+
+fn serialize_string<S: Serializer>(uid: &UID, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut buf = [0u8; DECIMAL_LEN];
+    let pos = encode(uid.0, DECIMAL, 0, &mut buf);
+    // only ASCII digits were written
+    serializer.serialize_str(core::str::from_utf8(&buf[pos..]).unwrap())
+}
+
+fn serialize_number<S: Serializer>(uid: &UID, serializer: S) -> Result<S::Ok, S::Error> {
+    #[cfg(not(quid_uid32))]
+    return serializer.serialize_u64(uid.0);
+    #[cfg(quid_uid32)]
+    return serializer.serialize_u32(uid.0);
+}
+
+struct UidVisitor;
+
+impl<'de> Visitor<'de> for UidVisitor {
+    type Value = UID;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a UID as an unsigned integer or a string")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<UID, E> {
+        UidTy::try_from(v)
+            .map(UID)
+            .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<UID, E> {
+        UidTy::try_from(v)
+            .map(UID)
+            .map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<UID, E> {
+        v.parse().map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+impl Serialize for UID {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serialize_string(self, serializer)
+        } else {
+            serialize_number(self, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for UID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<UID, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(UidVisitor)
+        } else {
+            as_number::deserialize(deserializer)
+        }
+    }
+}
+
+/// Always a number, for formats or consumers that handle 64-bit integers fine.
+pub mod as_number {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(uid: &UID, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_number(uid, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UID, D::Error> {
+        #[cfg(not(quid_uid32))]
+        return deserializer.deserialize_u64(UidVisitor);
+        #[cfg(quid_uid32)]
+        return deserializer.deserialize_u32(UidVisitor);
+    }
+}
+
+/// Always a decimal string, e.g. for binary formats read by JavaScript.
+pub mod as_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(uid: &UID, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_string(uid, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UID, D::Error> {
+        deserializer.deserialize_str(UidVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{UidTy, UID};
+    use serde_test::{assert_de_tokens, assert_tokens, Configure, Token};
+
+    #[cfg(not(quid_uid32))]
+    fn number(n: UidTy) -> Token {
+        Token::U64(n)
+    }
+    #[cfg(quid_uid32)]
+    fn number(n: UidTy) -> Token {
+        Token::U32(n)
+    }
+
+    #[test]
+    fn test_representation() {
+        assert_tokens(&UID(UidTy::MAX).compact(), &[number(UidTy::MAX)]);
+        let max = UidTy::MAX.to_string().leak();
+        assert_tokens(&UID(UidTy::MAX).readable(), &[Token::Str(max)]);
+        assert_tokens(&UID(0).readable(), &[Token::Str("0")]);
+        assert_de_tokens(&UID(5).readable(), &[Token::U64(5)]);
+        assert_de_tokens(&UID(31).readable(), &[Token::Str("0x1f")]);
+    }
+
+    #[test]
+    fn test_forced_representation() {
+        #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Forced {
+            #[serde(with = "crate::serde::as_number")]
+            number: UID,
+            #[serde(with = "crate::serde::as_string")]
+            string: UID,
+        }
+
+        let forced = Forced { number: UID(7), string: UID(8) };
+        let tokens = [
+            Token::Struct { name: "Forced", len: 2 },
+            Token::Str("number"),
+            number(7),
+            Token::Str("string"),
+            Token::Str("8"),
+            Token::StructEnd,
+        ];
+        assert_tokens(&forced.clone().readable(), &tokens);
+        assert_tokens(&forced.compact(), &tokens);
+    }
+}
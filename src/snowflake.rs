@@ -0,0 +1,457 @@
+use core::sync::atomic::Ordering;
+
+use crate::{
+    counter::AtomicUid,
+    generator::{SlotCache, SlotEntry, SlotIndex},
+    Clock, ClockError, ClockRegression, SystemClock, UidTy, UID,
+};
+
+/// 2020-01-01T00:00:00Z in milliseconds since the Unix epoch.
+pub const DEFAULT_EPOCH: u64 = 1_577_836_800_000;
+
+// sequence numbers a thread reserves from the shared state at once
+const DEFAULT_SEQUENCE_BLOCK: UidTy = 16;
+
+/// How the bits of a snowflake ID are split, from most to least significant:
+/// milliseconds since the epoch, node ID, then a per-millisecond sequence number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Layout {
+    pub timestamp_bits: u32,
+    pub node_bits: u32,
+    pub sequence_bits: u32,
+}
+
+/// The parts of a snowflake ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Snowflake {
+    /// Milliseconds since the Unix epoch, not the generator's.
+    pub timestamp: u64,
+    pub node: u64,
+    pub sequence: u64,
+}
+
+/// Hands out IDs made of a timestamp, a node ID and a sequence number, so that
+/// machines with different node IDs never hand out the same ID.
+///
+/// IDs from one generator are unique, and IDs from one thread are increasing.
+/// Like [`UidGenerator`](crate::UidGenerator) it is meant to live in a `static`.
+pub struct SnowflakeGenerator<C = SystemClock> {
+    // (milliseconds since the epoch << sequence_bits) + the next free sequence number.
+    // starts at 1, node 0 would turn key 0 into the invalid ID 0
+    next: AtomicUid,
+    slot: SlotIndex,
+    layout: Layout,
+    epoch: u64,
+    node: u64,
+    block: UidTy,
+    regression: ClockRegression,
+    clock: C,
+}
+
+#[derive(Clone, Copy)]
+struct SequenceBlock {
+    // the generator's address, generators of different clock types share the cache
+    owner: *const (),
+    // in the same (timestamp, sequence) space as `SnowflakeGenerator::next`
+    next: UidTy,
+    end: UidTy,
+}
+
+impl SlotEntry for SequenceBlock {
+    const EMPTY: SequenceBlock = SequenceBlock { owner: core::ptr::null(), next: 0, end: 0 };
+}
+
+std::thread_local! {
+    static BLOCKS: SlotCache<SequenceBlock> = const { SlotCache::new() };
+}
+
+impl Layout {
+    /// Twitter's layout: 41 bits of milliseconds (about 69 years), 1024 nodes
+    /// and 4096 IDs per millisecond and node.
+    pub const DEFAULT: Layout = Layout { timestamp_bits: 41, node_bits: 10, sequence_bits: 12 };
+
+    const fn validate(self) -> Self {
+        assert!(
+            self.timestamp_bits > 0
+                && self.sequence_bits > 0
+                && self.timestamp_bits + self.node_bits + self.sequence_bits <= UidTy::BITS,
+            "invalid snowflake layout"
+        );
+        self
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::DEFAULT
+    }
+}
+
+impl SnowflakeGenerator {
+    pub const fn new(node: u64) -> Self {
+        SnowflakeGenerator::with_clock(node, SystemClock)
+    }
+}
+
+impl<C: Clock> SnowflakeGenerator<C> {
+    pub const fn with_clock(node: u64, clock: C) -> Self {
+        SnowflakeGenerator {
+            next: AtomicUid::new(1),
+            slot: SlotIndex::new(),
+            layout: Layout::DEFAULT,
+            epoch: DEFAULT_EPOCH,
+            node,
+            block: DEFAULT_SEQUENCE_BLOCK,
+            regression: ClockRegression::Wait,
+            clock,
+        }
+        .validate_node()
+    }
+
+    pub const fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout.validate();
+        self.validate_node()
+    }
+
+    /// The timestamp stored in IDs counts milliseconds since `epoch`, itself in
+    /// milliseconds since the Unix epoch. Defaults to [`DEFAULT_EPOCH`].
+    pub const fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// How many sequence numbers a thread reserves at once. Whatever is left when
+    /// the clock moves on to the next millisecond is skipped.
+    pub const fn with_sequence_block(mut self, len: UidTy) -> Self {
+        assert!(len > 0, "invalid sequence block size");
+        self.block = len;
+        self
+    }
+
+    /// Defaults to [`ClockRegression::Wait`].
+    pub const fn with_clock_regression(mut self, policy: ClockRegression) -> Self {
+        self.regression = policy;
+        self
+    }
+
+    const fn validate_node(self) -> Self {
+        assert!(self.node >> self.layout.node_bits == 0, "node ID doesn't fit into the layout");
+        self
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn node(&self) -> u64 {
+        self.node
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn next_uid(&'static self) -> UID {
+        match self.try_next_uid() {
+            Ok(uid) => uid,
+            Err(ClockError::OutOfRange) => panic!("UID space exhausted"),
+            Err(ClockError::Backwards { .. }) => panic!("clock moved backwards"),
+        }
+    }
+
+    /// Fails once the timestamp no longer fits into the layout's timestamp bits, or when the
+    /// clock went backwards with [`ClockRegression::Error`]. If a millisecond's sequence numbers
+    /// run out, this waits for the next millisecond unless the policy is [`ClockRegression::Borrow`].
+    pub fn try_next_uid(&'static self) -> Result<UID, ClockError> {
+        let now = self.now()?;
+        let cached = BLOCKS.try_with(|blocks| {
+            let idx = self.slot.get();
+            let mut block = blocks.get(idx);
+            let owned = block.owner == (self as *const Self).cast();
+            // blocks from an earlier millisecond would put a stale timestamp into the ID,
+            // ones from a later millisecond mean the clock went backwards
+            let timestamp = block.next >> self.layout.sequence_bits;
+            let current = timestamp == now || (timestamp > now && self.regression == ClockRegression::Borrow);
+            if !owned || block.next == block.end || !current {
+                let (next, end) = self.claim(now, self.block)?;
+                block = SequenceBlock { owner: (self as *const Self).cast(), next, end };
+            }
+            let key = block.next;
+            block.next += 1;
+            blocks.set(idx, block);
+            Ok(key)
+        });
+        let key = match cached {
+            Ok(res) => res?,
+            Err(_) => self.claim(now, 1)?.0,
+        };
+        if key >> self.layout.sequence_bits >> self.layout.timestamp_bits != 0 {
+            // borrowed past the end of the timestamp bits
+            return Err(ClockError::OutOfRange);
+        }
+        Ok(UID(self.pack(key)))
+    }
+
+    /// Splits an ID from a generator with the same layout and epoch into its parts.
+    /// Timestamps past `u64::MAX` milliseconds saturate.
+    pub fn decode(&self, uid: &UID) -> Snowflake {
+        let Layout { timestamp_bits, node_bits, sequence_bits } = self.layout;
+        let raw = uid.0;
+        let field = |shift: u32, bits: u32| (raw >> shift) & (UidTy::MAX >> (UidTy::BITS - bits));
+        Snowflake {
+            timestamp: self.epoch.saturating_add(field(node_bits + sequence_bits, timestamp_bits)),
+            node: if node_bits == 0 { 0 } else { field(sequence_bits, node_bits) },
+            sequence: field(0, sequence_bits),
+        }
+    }
+
+    // claims up to `len` sequence numbers in the current millisecond, `now` or later
+    fn claim(&self, mut now: UidTy, len: UidTy) -> Result<(UidTy, UidTy), ClockError> {
+        let bits = self.layout.sequence_bits;
+        loop {
+            let floor = now << bits;
+            let ceil = (now + 1) << bits;
+            let borrow = self.regression == ClockRegression::Borrow;
+            let end = |start: UidTy| if borrow { start.saturating_add(len) } else { start + len.min(ceil - start) };
+            let res = self.next.fetch_update(Ordering::Relaxed, |cur| {
+                let start = cur.max(floor);
+                (borrow || start < ceil).then(|| end(start))
+            });
+            match res {
+                Ok(cur) => {
+                    let start = cur.max(floor);
+                    return Ok((start, end(start)));
+                }
+                // exactly `ceil` just means this millisecond is used up
+                Err(cur) if cur > ceil && self.regression == ClockRegression::Error => {
+                    // `now` may just be stale, with another thread having claimed a later
+                    // millisecond since. Only a clock that is still behind went backwards
+                    let last = (cur - 1) >> bits;
+                    let fresh = self.now()?;
+                    if fresh < last {
+                        return Err(ClockError::Backwards { millis: last - fresh });
+                    }
+                    now = fresh;
+                }
+                // wait for the first millisecond with sequence numbers left, not just past
+                // `now`, which is behind the counter after a regression
+                Err(cur) => now = self.wait_past((cur >> bits) - 1)?,
+            }
+        }
+    }
+
+    fn wait_past(&self, prev: UidTy) -> Result<UidTy, ClockError> {
+        loop {
+            let now = self.now()?;
+            if now > prev {
+                return Ok(now);
+            }
+            self.clock.wait_past(self.epoch + prev);
+        }
+    }
+
+    // milliseconds since our epoch, 0 if the clock is before it
+    fn now(&self) -> Result<UidTy, ClockError> {
+        let now = self.clock.now_millis().saturating_sub(self.epoch);
+        if now >> self.layout.timestamp_bits != 0 {
+            return Err(ClockError::OutOfRange);
+        }
+        Ok(now)
+    }
+
+    fn pack(&self, key: UidTy) -> UidTy {
+        let Layout { node_bits, sequence_bits, .. } = self.layout;
+        let timestamp = key >> sequence_bits;
+        let sequence = key & ((1 << sequence_bits) - 1);
+        (timestamp << (node_bits + sequence_bits)) | (self.node << sequence_bits) | sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, SnowflakeGenerator};
+    use crate::{clock::FakeClock, Clock, ClockError, ClockRegression, UidTy};
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unix_millis() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+    }
+
+    #[test]
+    fn test_decode() {
+        static GEN: SnowflakeGenerator = SnowflakeGenerator::new(513);
+
+        let before = unix_millis();
+        let a = GEN.next_uid();
+        let b = GEN.next_uid();
+        let after = unix_millis();
+
+        let parts = GEN.decode(&a);
+        assert!(before <= parts.timestamp && parts.timestamp <= after);
+        assert_eq!(parts.node, 513);
+        assert!(UidTy::from(b) > UidTy::from(a));
+    }
+
+    #[test]
+    fn test_unique_across_threads() {
+        static GEN: SnowflakeGenerator = SnowflakeGenerator::new(3)
+            .with_layout(Layout { timestamp_bits: 41, node_bits: 2, sequence_bits: 4 })
+            .with_sequence_block(3);
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    let ids: Vec<UidTy> = (0..500).map(|_| GEN.next_uid().into()).collect();
+                    assert!(ids.windows(2).all(|w| w[0] < w[1]), "IDs from one thread should increase");
+                    ids
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for t in threads {
+            for id in t.join().unwrap() {
+                assert!(seen.insert(id), "duplicate ID {}", id);
+                assert_eq!(GEN.decode(&crate::UID(id)).node, 3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sequence_exhaustion_waits() {
+        static GEN: SnowflakeGenerator =
+            SnowflakeGenerator::new(0).with_layout(Layout { timestamp_bits: 41, node_bits: 0, sequence_bits: 1 });
+
+        let first = GEN.decode(&GEN.next_uid()).timestamp;
+        for _ in 0..8 {
+            GEN.next_uid();
+        }
+        // two IDs per millisecond
+        assert!(GEN.decode(&GEN.next_uid()).timestamp >= first + 4);
+    }
+
+    #[test]
+    fn test_timestamp_overflow() {
+        static GEN: SnowflakeGenerator = SnowflakeGenerator::new(0)
+            .with_epoch(0)
+            .with_layout(Layout { timestamp_bits: 20, node_bits: 0, sequence_bits: 12 });
+
+        assert_eq!(GEN.try_next_uid(), Err(ClockError::OutOfRange));
+    }
+
+    #[test]
+    fn test_never_zero_at_epoch() {
+        static AT: SnowflakeGenerator<FakeClock> =
+            SnowflakeGenerator::with_clock(0, FakeClock::at(1000)).with_epoch(1000);
+        static BEFORE: SnowflakeGenerator<FakeClock> =
+            SnowflakeGenerator::with_clock(0, FakeClock::at(1000)).with_epoch(u64::MAX);
+
+        assert_eq!(UidTy::from(AT.next_uid()), 1);
+        assert_eq!(UidTy::from(BEFORE.next_uid()), 1);
+    }
+
+    #[test]
+    fn test_decode_saturates() {
+        static GEN: SnowflakeGenerator = SnowflakeGenerator::new(0).with_epoch(u64::MAX);
+
+        assert_eq!(GEN.decode(&crate::UID(0xffff_ffff_ffff)).timestamp, u64::MAX);
+    }
+
+    #[test]
+    fn test_regression_wait() {
+        static GEN: SnowflakeGenerator<FakeClock> = SnowflakeGenerator::with_clock(0, FakeClock::at(1000)).with_epoch(0);
+
+        let a = GEN.next_uid();
+        GEN.clock().set(995);
+        let b = GEN.next_uid();
+
+        assert!(UidTy::from(b) > UidTy::from(a));
+        assert_eq!(GEN.decode(&b).timestamp, 1000);
+        assert_eq!(GEN.clock().waits(), 1);
+    }
+
+    #[test]
+    fn test_regression_borrow() {
+        static GEN: SnowflakeGenerator<FakeClock> = SnowflakeGenerator::with_clock(0, FakeClock::at(1000))
+            .with_epoch(0)
+            .with_layout(Layout { timestamp_bits: 41, node_bits: 10, sequence_bits: 2 })
+            .with_clock_regression(ClockRegression::Borrow);
+
+        let a = GEN.next_uid();
+        GEN.clock().set(900);
+        let ids: Vec<UidTy> = (0..8).map(|_| GEN.next_uid().into()).collect();
+
+        assert!(UidTy::from(a) < ids[0] && ids.windows(2).all(|w| w[0] < w[1]));
+        // four IDs per millisecond, the sequence overflows into the timestamp
+        assert_eq!(GEN.decode(&crate::UID(ids[7])).timestamp, 1002);
+        assert_eq!(GEN.clock().waits(), 0);
+    }
+
+    #[test]
+    fn test_regression_error() {
+        static GEN: SnowflakeGenerator<FakeClock> = SnowflakeGenerator::with_clock(0, FakeClock::at(1000))
+            .with_epoch(0)
+            .with_layout(Layout { timestamp_bits: 41, node_bits: 10, sequence_bits: 1 })
+            .with_clock_regression(ClockRegression::Error);
+
+        GEN.next_uid();
+        GEN.next_uid();
+        // a used up millisecond isn't a regression
+        assert_eq!(GEN.decode(&GEN.next_uid()).timestamp, 1001);
+        assert_eq!(GEN.clock().waits(), 1);
+
+        GEN.clock().set(996);
+        assert_eq!(GEN.try_next_uid(), Err(ClockError::Backwards { millis: 5 }));
+        GEN.clock().set(1001);
+        assert!(GEN.try_next_uid().is_ok());
+    }
+
+    // never goes backwards, but moves on every few reads, so threads often hold
+    // readings a millisecond apart
+    struct TickingClock(AtomicU64);
+
+    impl Clock for TickingClock {
+        fn now_millis(&self) -> u64 {
+            1000 + self.0.fetch_add(1, Ordering::Relaxed) / 4
+        }
+    }
+
+    #[test]
+    fn test_regression_error_across_threads() {
+        static GEN: SnowflakeGenerator<TickingClock> = SnowflakeGenerator::with_clock(0, TickingClock(AtomicU64::new(0)))
+            .with_epoch(0)
+            .with_layout(Layout { timestamp_bits: 41, node_bits: 0, sequence_bits: 4 })
+            .with_sequence_block(2)
+            .with_clock_regression(ClockRegression::Error);
+
+        let start = Arc::new(Barrier::new(4));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let start = Arc::clone(&start);
+                thread::spawn(move || {
+                    start.wait();
+                    (0..20_000).map(|_| GEN.try_next_uid().map(UidTy::from)).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for t in threads {
+            for id in t.join().unwrap() {
+                // the clock never went backwards, other threads were just ahead
+                let id = id.unwrap();
+                assert!(seen.insert(id), "duplicate ID {}", id);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "node ID doesn't fit into the layout")]
+    fn test_node_must_fit() {
+        let _ = SnowflakeGenerator::new(4).with_layout(Layout { timestamp_bits: 41, node_bits: 2, sequence_bits: 12 });
+    }
+}
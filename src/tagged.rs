@@ -0,0 +1,237 @@
+use core::{cmp::Ordering, hash::{Hash, Hasher}, marker::PhantomData, num::{NonZeroU32, NonZeroU64}};
+
+#[cfg(feature = "fmt")]
+use nostd::fmt;
+
+use crate::{UidExhausted, UidGenerator, UidRepr, UidTy, GLOBAL_NEXT_UID};
+
+/// A UID that can only be compared with UIDs of the same tag type `T`, stored as `R`.
+///
+/// `T` is only a marker: none of the trait impls require anything of it.
+pub struct Uid<T: ?Sized, R = UidTy> {
+    raw: R,
+    tag: PhantomData<fn() -> T>,
+}
+
+// untagged flavours, use `Uid<Tag, u32>` etc. for tagged ones
+pub type Uid32 = Uid<(), u32>;
+pub type Uid64 = Uid<(), u64>;
+pub type Uid128 = Uid<(), u128>;
+pub type NonZeroUid32 = Uid<(), NonZeroU32>;
+pub type NonZeroUid64 = Uid<(), NonZeroU64>;
+
+/// Marks a type as usable as a [`Uid`] tag and picks the counter its IDs come from.
+///
+/// The default shares the counter behind [`crate::UID::new`]. To give a tag its own dense ID space:
+///
+/// ```
+/// use quid::{Uid, UidGenerator, UidTag};
+///
+/// struct Mesh;
+///
+/// impl UidTag for Mesh {
+///     fn generator() -> &'static UidGenerator {
+///         static GEN: UidGenerator = UidGenerator::new();
+///         &GEN
+///     }
+/// }
+///
+/// let mesh: Uid<Mesh> = Uid::new();
+/// ```
+pub trait UidTag {
+    fn generator() -> &'static UidGenerator {
+        &GLOBAL_NEXT_UID
+    }
+}
+
+// untagged flavours like `Uid32` share the global counter
+impl UidTag for () {}
+
+impl<T: ?Sized + UidTag, R: UidRepr> Uid<T, R> {
+    /// Panics once the generator is exhausted or hands out an ID that doesn't fit into `R`.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        match Self::try_new() {
+            Ok(uid) => uid,
+            Err(_) => panic!("UID space exhausted"),
+        }
+    }
+
+    pub fn try_new() -> Result<Self, UidExhausted> {
+        let gen = T::generator();
+        let raw = gen.try_next_raw()?;
+        R::from_uid_ty(raw).map(Self::from_raw).ok_or_else(|| gen.exhausted())
+    }
+}
+
+impl<T: ?Sized, R: UidRepr> Uid<T, R> {
+    pub const fn from_raw(raw: R) -> Self {
+        Uid { raw, tag: PhantomData }
+    }
+
+    pub const fn raw(self) -> R {
+        self.raw
+    }
+}
+
+impl<T: ?Sized, R: UidRepr> Clone for Uid<T, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized, R: UidRepr> Copy for Uid<T, R> {}
+
+impl<T: ?Sized, R: UidRepr> PartialEq for Uid<T, R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<T: ?Sized, R: UidRepr> Eq for Uid<T, R> {}
+
+impl<T: ?Sized, R: UidRepr> PartialOrd for Uid<T, R> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ?Sized, R: UidRepr> Ord for Uid<T, R> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.raw.cmp(&other.raw)
+    }
+}
+
+impl<T: ?Sized, R: UidRepr> Hash for Uid<T, R> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state)
+    }
+}
+
+macro_rules! impl_raw_conversions {
+    ($($ty:ty),*) => {
+        $(
+            impl<T: ?Sized> From<$ty> for Uid<T, $ty> {
+                fn from(raw: $ty) -> Self {
+                    Self::from_raw(raw)
+                }
+            }
+
+            impl<T: ?Sized> From<Uid<T, $ty>> for $ty {
+                fn from(uid: Uid<T, $ty>) -> $ty {
+                    uid.raw
+                }
+            }
+        )*
+    };
+}
+
+impl_raw_conversions!(u32, u64, u128, NonZeroU32, NonZeroU64);
+
+#[cfg(feature = "fmt")]
+impl<T: ?Sized, R: UidRepr> fmt::Debug for Uid<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Uid<{}>({})", core::any::type_name::<T>(), self.raw)
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl<T: ?Sized, R: UidRepr> fmt::Display for Uid<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.raw, f)
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl<T: ?Sized, R: UidRepr> fmt::LowerHex for Uid<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.raw, f)
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl<T: ?Sized, R: UidRepr> fmt::UpperHex for Uid<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.raw, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NonZeroUid64, Uid, Uid128, Uid32, UidTag};
+    use crate::{UidGenerator, UidTy};
+    use core::num::NonZeroU64;
+    use std::collections::BTreeSet;
+
+    // deliberately implements nothing but the tag trait
+    struct Texture;
+
+    impl UidTag for Texture {
+        fn generator() -> &'static UidGenerator {
+            static GEN: UidGenerator = UidGenerator::new();
+            &GEN
+        }
+    }
+
+    #[test]
+    fn test_tagged_uid_uses_own_counter() {
+        let a: Uid<Texture> = Uid::new();
+        let b = a;
+
+        assert_eq!(a, b);
+        assert_ne!(Uid::<Texture>::new(), a);
+        assert!(a.raw() <= 512, "Texture IDs should start from their own counter");
+        assert_eq!(core::mem::size_of::<Uid<Texture>>(), core::mem::size_of::<UidTy>());
+    }
+
+    #[test]
+    fn test_tagged_uid_raw_conversions() {
+        let uid = Uid::<Texture>::from_raw(42);
+        let raw: UidTy = uid.into();
+
+        assert_eq!(raw, 42);
+        assert_eq!(Uid::<Texture>::from(raw), uid);
+
+        let set: BTreeSet<Uid<Texture>> = [3, 1, 2].into_iter().map(Uid::from_raw).collect();
+        assert_eq!(set.iter().map(|u| u.raw()).collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_uid_flavours() {
+        assert_eq!(core::mem::size_of::<Option<NonZeroUid64>>(), 8);
+        assert_eq!(core::mem::size_of::<Uid32>(), 4);
+
+        let a = NonZeroUid64::new();
+        let b = Uid128::new();
+        assert_ne!(b.raw(), a.raw().get() as u128);
+
+        let c = Uid::<Texture, NonZeroU64>::new();
+        assert!(c.raw().get() <= 512);
+    }
+
+    #[test]
+    #[cfg(not(quid_uid32))]
+    fn test_narrow_flavour_exhaustion() {
+        use crate::{BlockSize, Monotonicity, UidExhausted};
+        use core::num::NonZeroU32;
+
+        struct Big;
+
+        impl UidTag for Big {
+            fn generator() -> &'static UidGenerator {
+                static GEN: UidGenerator = UidGenerator::new()
+                    .with_block_size(BlockSize::Fixed(2))
+                    .with_monotonicity(Monotonicity::PerThread);
+                &GEN
+            }
+        }
+
+        // skip ahead to the end of the 32-bit range
+        Big::generator().reserve(u32::MAX as UidTy - 1);
+
+        let small: Result<Uid<Big, u32>, _> = Uid::try_new();
+        assert_eq!(small.map(|u| u.raw()), Ok(u32::MAX));
+        let nonzero: Result<Uid<Big, NonZeroU32>, _> = Uid::try_new();
+        assert_eq!(nonzero, Err(UidExhausted));
+    }
+}
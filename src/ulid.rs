@@ -0,0 +1,360 @@
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use core::{cell::Cell, hash::BuildHasher};
+#[cfg(feature = "fmt")]
+use nostd::fmt;
+
+#[cfg(feature = "fmt")]
+use crate::encoding::BASE32;
+#[cfg(feature = "std")]
+use crate::{
+    generator::{SlotCache, SlotEntry, SlotIndex},
+    Clock, ClockError, ClockRegression, SystemClock,
+};
+use crate::{
+    encoding::{parse_digits, Encoding},
+    ParseUidError, Uid128,
+};
+
+const RANDOM_BITS: u32 = 80;
+#[cfg(feature = "fmt")]
+const TEXT_LEN: usize = 26;
+
+/// A 128-bit ID made of a 48-bit millisecond Unix timestamp and 80 random bits.
+///
+/// Its text form is 26 characters of Crockford base32, which sort the same way as the IDs.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ulid(u128);
+
+impl Ulid {
+    /// Panics if `timestamp` doesn't fit into 48 bits, `random` is cut to 80 bits.
+    pub const fn from_parts(timestamp: u64, random: u128) -> Ulid {
+        assert!(timestamp >> 48 == 0, "ULID timestamp out of range");
+        Ulid(((timestamp as u128) << RANDOM_BITS) | (random & ((1 << RANDOM_BITS) - 1)))
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub const fn timestamp(&self) -> u64 {
+        (self.0 >> RANDOM_BITS) as u64
+    }
+
+    pub const fn random(&self) -> u128 {
+        self.0 & ((1 << RANDOM_BITS) - 1)
+    }
+
+    pub const fn to_u128(self) -> u128 {
+        self.0
+    }
+
+    pub const fn from_u128(n: u128) -> Ulid {
+        Ulid(n)
+    }
+}
+
+impl From<Ulid> for u128 {
+    fn from(ulid: Ulid) -> u128 {
+        ulid.0
+    }
+}
+
+impl From<u128> for Ulid {
+    fn from(n: u128) -> Ulid {
+        Ulid(n)
+    }
+}
+
+impl From<Ulid> for Uid128 {
+    fn from(ulid: Ulid) -> Uid128 {
+        Uid128::from_raw(ulid.0)
+    }
+}
+
+impl From<Uid128> for Ulid {
+    fn from(uid: Uid128) -> Ulid {
+        Ulid(uid.raw())
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl fmt::Display for Ulid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = [0u8; TEXT_LEN];
+        for (i, c) in buf.iter_mut().enumerate() {
+            let shift = 5 * (TEXT_LEN - 1 - i);
+            *c = BASE32[((self.0 >> shift) & 31) as usize];
+        }
+        // BASE32 is ASCII
+        f.pad(core::str::from_utf8(&buf).map_err(|_| fmt::Error)?)
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl fmt::Debug for Ulid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ulid({})", self)
+    }
+}
+
+/// Crockford base32 like [`UID::from_base32`](crate::UID::from_base32), exactly 26 characters.
+impl FromStr for Ulid {
+    type Err = ParseUidError;
+
+    fn from_str(s: &str) -> Result<Ulid, ParseUidError> {
+        if s.is_empty() {
+            return Err(ParseUidError::Empty);
+        }
+        if s.len() != 26 {
+            return Err(ParseUidError::InvalidLength { len: s.len() });
+        }
+        parse_digits(s, Encoding::Base32).map(Ulid)
+    }
+}
+
+impl TryFrom<&str> for Ulid {
+    type Error = ParseUidError;
+
+    fn try_from(s: &str) -> Result<Ulid, ParseUidError> {
+        s.parse()
+    }
+}
+
+#[cfg(feature = "std")]
+static GLOBAL_ULID: UlidGenerator = UlidGenerator::new();
+
+#[cfg(feature = "std")]
+impl Ulid {
+    // a fresh ID as the default value would be surprising
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Ulid {
+        GLOBAL_ULID.next_ulid()
+    }
+
+    pub fn try_new() -> Result<Ulid, ClockError> {
+        GLOBAL_ULID.try_next_ulid()
+    }
+}
+
+/// Hands out monotonic ULIDs: within a millisecond, each thread increments the
+/// random part of its previous ULID instead of drawing a new one.
+///
+/// There is no shared state between threads, so ULIDs are increasing per thread
+/// and unique across threads only as far as 80 random bits go. The random bits
+/// come from a fast per-thread generator seeded by std's `RandomState`, they
+/// are not suitable as secrets.
+#[cfg(feature = "std")]
+pub struct UlidGenerator<C = SystemClock> {
+    slot: SlotIndex,
+    pub(crate) regression: ClockRegression,
+    clock: C,
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Copy)]
+struct LastUlid {
+    // the generator's address
+    owner: *const (),
+    last: u128,
+}
+
+#[cfg(feature = "std")]
+impl SlotEntry for LastUlid {
+    const EMPTY: LastUlid = LastUlid { owner: core::ptr::null(), last: 0 };
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static LAST: SlotCache<LastUlid> = const { SlotCache::new() };
+    // 0 until seeded
+    static RNG: Cell<u64> = const { Cell::new(0) };
+}
+
+// wyrand
+#[cfg(feature = "std")]
+pub(crate) fn next_random() -> u64 {
+    RNG.with(|state| {
+        let mut s = state.get();
+        if s == 0 {
+            s = std::collections::hash_map::RandomState::new().hash_one(std::thread::current().id()) | 1;
+        }
+        s = s.wrapping_add(0xa076_1d64_78bd_642f);
+        state.set(s);
+        let t = u128::from(s) * u128::from(s ^ 0xe703_7ed1_a0b4_28db);
+        ((t >> 64) ^ t) as u64
+    })
+}
+
+#[cfg(feature = "std")]
+impl UlidGenerator {
+    pub const fn new() -> Self {
+        UlidGenerator::with_clock(SystemClock)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for UlidGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> UlidGenerator<C> {
+    pub const fn with_clock(clock: C) -> Self {
+        UlidGenerator { slot: SlotIndex::new(), regression: ClockRegression::Borrow, clock }
+    }
+
+    /// Defaults to [`ClockRegression::Borrow`], which keeps incrementing the thread's
+    /// previous ULID, as the ULID spec does.
+    pub const fn with_clock_regression(mut self, policy: ClockRegression) -> Self {
+        self.regression = policy;
+        self
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn next_ulid(&'static self) -> Ulid {
+        match self.try_next_ulid() {
+            Ok(ulid) => ulid,
+            Err(ClockError::OutOfRange) => panic!("ULID space exhausted"),
+            Err(ClockError::Backwards { .. }) => panic!("clock moved backwards"),
+        }
+    }
+
+    /// Fails once the clock is past the 48-bit timestamp, or when it went backwards
+    /// with [`ClockRegression::Error`].
+    pub fn try_next_ulid(&'static self) -> Result<Ulid, ClockError> {
+        self.try_next_raw(RANDOM_BITS).map(Ulid)
+    }
+
+    // a 48-bit timestamp followed by `random_bits` random bits. a generator has to stick to one width
+    pub(crate) fn try_next_raw(&'static self, random_bits: u32) -> Result<u128, ClockError> {
+        let owner = (self as *const Self).cast();
+        let prev = LAST
+            .try_with(|cache| cache.get(self.slot.get()))
+            .ok()
+            .filter(|prev| prev.owner == owner)
+            .map(|prev| prev.last);
+
+        let mut now = self.clock.now_millis();
+        let next = match prev {
+            Some(last) if now <= (last >> random_bits) as u64 => {
+                let last_ts = (last >> random_bits) as u64;
+                if now < last_ts {
+                    match self.regression {
+                        ClockRegression::Borrow => {}
+                        ClockRegression::Error => return Err(ClockError::Backwards { millis: last_ts - now }),
+                        ClockRegression::Wait => {
+                            while now < last_ts {
+                                self.clock.wait_past(last_ts - 1);
+                                now = self.clock.now_millis();
+                            }
+                        }
+                    }
+                }
+                if now > last_ts {
+                    fresh(now, random_bits)?
+                } else {
+                    // carries into the timestamp once the random part is used up
+                    let next = last.checked_add(1).ok_or(ClockError::OutOfRange)?;
+                    if next >> random_bits >> 48 != 0 {
+                        return Err(ClockError::OutOfRange);
+                    }
+                    next
+                }
+            }
+            _ => fresh(now, random_bits)?,
+        };
+
+        let _ = LAST.try_with(|cache| cache.set(self.slot.get(), LastUlid { owner, last: next }));
+        Ok(next)
+    }
+}
+
+#[cfg(feature = "std")]
+fn fresh(now: u64, random_bits: u32) -> Result<u128, ClockError> {
+    if now >> 48 != 0 {
+        return Err(ClockError::OutOfRange);
+    }
+    let random = (u128::from(next_random()) << 64) | u128::from(next_random());
+    Ok((u128::from(now) << random_bits) | (random & ((1 << random_bits) - 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ulid, UlidGenerator};
+    use crate::{clock::FakeClock, ClockError, ClockRegression, ParseUidError, Uid128};
+    use std::collections::HashSet;
+    use std::thread;
+
+    #[test]
+    fn test_text_form() {
+        let ulid: Ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse().unwrap();
+        assert_eq!(ulid.timestamp(), 1_469_922_850_259);
+        assert_eq!(ulid.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+        assert_eq!("01arz3ndektsv4rrffq69g5fav".parse(), Ok(ulid));
+
+        assert_eq!(Ulid::from_u128(0).to_string(), "00000000000000000000000000");
+        assert_eq!(Ulid::from_u128(u128::MAX).to_string(), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+        assert_eq!("80000000000000000000000000".parse::<Ulid>(), Err(ParseUidError::Overflow));
+        assert_eq!("-".repeat(26).parse::<Ulid>(), Err(ParseUidError::Empty));
+        assert_eq!("01AR-Z3".parse::<Ulid>(), Err(ParseUidError::InvalidLength { len: 7 }));
+        assert_eq!("01ARZ3NDEKTSV4RRFFQ69G5FAV0".parse::<Ulid>(), Err(ParseUidError::InvalidLength { len: 27 }));
+
+        let uid = Uid128::from(ulid);
+        assert_eq!(Ulid::from(uid), ulid);
+    }
+
+    #[test]
+    fn test_monotonic_within_millisecond() {
+        static GEN: UlidGenerator<FakeClock> = UlidGenerator::with_clock(FakeClock::at(1000));
+
+        let a = GEN.next_ulid();
+        let b = GEN.next_ulid();
+        assert_eq!(b.to_u128(), a.to_u128() + 1);
+
+        GEN.clock().set(1001);
+        let c = GEN.next_ulid();
+        assert_eq!(c.timestamp(), 1001);
+        assert!(c > b);
+
+        // borrowing keeps counting from the last ULID
+        GEN.clock().set(990);
+        assert_eq!(GEN.next_ulid().to_u128(), c.to_u128() + 1);
+    }
+
+    #[test]
+    fn test_regression_policies() {
+        static WAIT: UlidGenerator<FakeClock> =
+            UlidGenerator::with_clock(FakeClock::at(1000)).with_clock_regression(ClockRegression::Wait);
+        static ERROR: UlidGenerator<FakeClock> =
+            UlidGenerator::with_clock(FakeClock::at(1000)).with_clock_regression(ClockRegression::Error);
+
+        let a = WAIT.next_ulid();
+        WAIT.clock().set(997);
+        let b = WAIT.next_ulid();
+        assert_eq!(WAIT.clock().waits(), 1);
+        assert_eq!(b.to_u128(), a.to_u128() + 1);
+
+        ERROR.next_ulid();
+        ERROR.clock().set(997);
+        assert_eq!(ERROR.try_next_ulid(), Err(ClockError::Backwards { millis: 3 }));
+    }
+
+    #[test]
+    fn test_unique_across_threads() {
+        let threads: Vec<_> = (0..4)
+            .map(|_| thread::spawn(|| (0..1000).map(|_| Ulid::new()).collect::<Vec<_>>()))
+            .collect();
+
+        let mut seen = HashSet::new();
+        for t in threads {
+            let ids = t.join().unwrap();
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+            for id in ids {
+                assert!(seen.insert(id));
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use core::str::FromStr;
+#[cfg(feature = "fmt")]
+use nostd::fmt;
+
+#[cfg(feature = "std")]
+use crate::{
+    ulid::next_random, Clock, ClockError, ClockRegression, SystemClock, UidExhausted, UidGenerator, UlidGenerator,
+};
+use crate::{
+    encoding::{digit_value, Encoding},
+    ParseUidError,
+};
+
+const VERSION_SHIFT: u32 = 76;
+const VARIANT: u128 = 0b10 << 62;
+// where the 74 bits around the version and variant fields go
+const LOW_BITS: u32 = 62;
+const MID_BITS: u32 = 12;
+
+/// An RFC 9562 UUID, printed in the usual hyphenated lowercase hex form.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid(u128);
+
+impl Uuid {
+    pub const NIL: Uuid = Uuid(0);
+
+    pub const fn from_u128(n: u128) -> Uuid {
+        Uuid(n)
+    }
+
+    pub const fn to_u128(self) -> u128 {
+        self.0
+    }
+
+    pub const fn from_bytes(bytes: [u8; 16]) -> Uuid {
+        Uuid(u128::from_be_bytes(bytes))
+    }
+
+    pub const fn to_bytes(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    pub const fn version(&self) -> u8 {
+        ((self.0 >> VERSION_SHIFT) & 0xf) as u8
+    }
+
+    /// A version 8 UUID from its three custom fields: 48, 12 and 62 bits from the
+    /// top, with the version and variant in between. Higher bits of each field are cut.
+    pub const fn new_v8(custom_a: u64, custom_b: u16, custom_c: u64) -> Uuid {
+        Uuid::with_version(8, custom_a, ((custom_b as u128) << LOW_BITS) | custom_c as u128)
+    }
+
+    // a 48-bit field, the version, then 74 bits split around the variant
+    const fn with_version(version: u8, high: u64, low: u128) -> Uuid {
+        let high = (high as u128 & ((1 << 48) - 1)) << 80;
+        let mid = (low >> LOW_BITS) & ((1 << MID_BITS) - 1);
+        let low = low & ((1 << LOW_BITS) - 1);
+        Uuid(high | ((version as u128) << VERSION_SHIFT) | (mid << 64) | VARIANT | low)
+    }
+
+    /// Milliseconds since the Unix epoch, for version 7 UUIDs and the
+    /// ones from [`UuidV8Generator`].
+    pub const fn timestamp(&self) -> u64 {
+        (self.0 >> 80) as u64
+    }
+}
+
+impl From<Uuid> for u128 {
+    fn from(uuid: Uuid) -> u128 {
+        uuid.0
+    }
+}
+
+impl From<u128> for Uuid {
+    fn from(n: u128) -> Uuid {
+        Uuid(n)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<Uuid> for ::uuid::Uuid {
+    fn from(uuid: Uuid) -> ::uuid::Uuid {
+        ::uuid::Uuid::from_u128(uuid.0)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<::uuid::Uuid> for Uuid {
+    fn from(uuid: ::uuid::Uuid) -> Uuid {
+        Uuid(uuid.as_u128())
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            n >> 96,
+            (n >> 80) & 0xffff,
+            (n >> 64) & 0xffff,
+            (n >> 48) & 0xffff,
+            n & 0xffff_ffff_ffff
+        )
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl fmt::Debug for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Uuid({})", self)
+    }
+}
+
+/// The hyphenated form, or 32 hex digits without hyphens, in either case.
+impl FromStr for Uuid {
+    type Err = ParseUidError;
+
+    fn from_str(s: &str) -> Result<Uuid, ParseUidError> {
+        if s.is_empty() {
+            return Err(ParseUidError::Empty);
+        }
+        let hyphenated = s.len() == 36;
+        if !hyphenated && s.len() != 32 {
+            return Err(ParseUidError::InvalidLength { len: s.len() });
+        }
+
+        let mut n: u128 = 0;
+        for (index, ch) in s.char_indices() {
+            if hyphenated && matches!(index, 8 | 13 | 18 | 23) {
+                if ch != '-' {
+                    return Err(ParseUidError::InvalidChar { index, ch });
+                }
+                continue;
+            }
+            let digit = u8::try_from(ch)
+                .ok()
+                .and_then(|c| digit_value(Encoding::Hex, c))
+                .ok_or(ParseUidError::InvalidChar { index, ch })?;
+            n = (n << 4) | u128::from(digit);
+        }
+        Ok(Uuid(n))
+    }
+}
+
+impl TryFrom<&str> for Uuid {
+    type Error = ParseUidError;
+
+    fn try_from(s: &str) -> Result<Uuid, ParseUidError> {
+        s.parse()
+    }
+}
+
+#[cfg(feature = "std")]
+static GLOBAL_V7: UuidV7Generator = UuidV7Generator::new();
+#[cfg(feature = "std")]
+static GLOBAL_V8: UuidV8Generator = UuidV8Generator::new().with_counter(&crate::GLOBAL_NEXT_UID);
+
+#[cfg(feature = "std")]
+impl Uuid {
+    pub fn now_v7() -> Uuid {
+        GLOBAL_V7.next_uuid()
+    }
+
+    /// A version 8 UUID holding the time and the next value of the counter behind [`UID::new`](crate::UID::new).
+    pub fn now_v8() -> Uuid {
+        GLOBAL_V8.next_uuid()
+    }
+}
+
+/// Hands out version 7 UUIDs: a millisecond Unix timestamp followed by 74 random bits.
+///
+/// Like [`UlidGenerator`], each thread increments its previous UUID within a millisecond,
+/// so UUIDs from one thread are increasing.
+#[cfg(feature = "std")]
+pub struct UuidV7Generator<C = SystemClock> {
+    inner: UlidGenerator<C>,
+}
+
+#[cfg(feature = "std")]
+impl UuidV7Generator {
+    pub const fn new() -> Self {
+        UuidV7Generator::with_clock(SystemClock)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for UuidV7Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> UuidV7Generator<C> {
+    pub const fn with_clock(clock: C) -> Self {
+        UuidV7Generator { inner: UlidGenerator::with_clock(clock) }
+    }
+
+    /// Defaults to [`ClockRegression::Borrow`].
+    pub const fn with_clock_regression(mut self, policy: ClockRegression) -> Self {
+        self.inner.regression = policy;
+        self
+    }
+
+    pub fn clock(&self) -> &C {
+        self.inner.clock()
+    }
+
+    pub fn next_uuid(&'static self) -> Uuid {
+        match self.try_next_uuid() {
+            Ok(uuid) => uuid,
+            Err(ClockError::OutOfRange) => panic!("UUID space exhausted"),
+            Err(ClockError::Backwards { .. }) => panic!("clock moved backwards"),
+        }
+    }
+
+    pub fn try_next_uuid(&'static self) -> Result<Uuid, ClockError> {
+        let raw = self.inner.try_next_raw(MID_BITS + LOW_BITS)?;
+        Ok(Uuid::with_version(7, (raw >> (MID_BITS + LOW_BITS)) as u64, raw))
+    }
+}
+
+/// Hands out version 8 UUIDs: a millisecond Unix timestamp in the first 48 bits,
+/// then either 74 random bits, or 10 zero bits and a [`UID`](crate::UID) from the
+/// generator passed to [`with_counter`](Self::with_counter).
+///
+/// With a counter, UUIDs are unique within the process without relying on
+/// randomness, but not across processes.
+#[cfg(feature = "std")]
+pub struct UuidV8Generator<C = SystemClock> {
+    counter: Option<&'static UidGenerator>,
+    clock: C,
+}
+
+#[cfg(feature = "std")]
+impl UuidV8Generator {
+    pub const fn new() -> Self {
+        UuidV8Generator::with_clock(SystemClock)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for UuidV8Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> UuidV8Generator<C> {
+    pub const fn with_clock(clock: C) -> Self {
+        UuidV8Generator { counter: None, clock }
+    }
+
+    pub const fn with_counter(mut self, counter: &'static UidGenerator) -> Self {
+        self.counter = Some(counter);
+        self
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn next_uuid(&self) -> Uuid {
+        match self.try_next_uuid() {
+            Ok(uuid) => uuid,
+            Err(_) => panic!("UID space exhausted"),
+        }
+    }
+
+    /// Only fails when the counter is exhausted.
+    pub fn try_next_uuid(&self) -> Result<Uuid, UidExhausted> {
+        let low = match self.counter {
+            Some(counter) => u128::from(counter.try_next_raw()?),
+            None => (u128::from(next_random()) << 64) | u128::from(next_random()),
+        };
+        Ok(Uuid::with_version(8, self.clock.now_millis(), low))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Uuid, UuidV7Generator, UuidV8Generator};
+    use crate::{clock::FakeClock, ParseUidError, UidGenerator};
+
+    #[test]
+    fn test_text_form() {
+        let s = "017f22e2-79b0-7cc3-98c4-dc0c0c07398f";
+        let uuid: Uuid = s.parse().unwrap();
+        assert_eq!(uuid.to_string(), s);
+        assert_eq!(uuid.version(), 7);
+        assert_eq!(uuid.timestamp(), 0x017f_22e2_79b0);
+        assert_eq!("017F22E279B07CC398C4DC0C0C07398F".parse(), Ok(uuid));
+
+        assert_eq!("017f22e2".parse::<Uuid>(), Err(ParseUidError::InvalidLength { len: 8 }));
+        assert_eq!(
+            "017f22e2+79b0-7cc3-98c4-dc0c0c07398f".parse::<Uuid>(),
+            Err(ParseUidError::InvalidChar { index: 8, ch: '+' })
+        );
+        assert_eq!(Uuid::NIL.to_string(), "00000000-0000-0000-0000-000000000000");
+    }
+
+    #[test]
+    fn test_v7() {
+        static GEN: UuidV7Generator<FakeClock> = UuidV7Generator::with_clock(FakeClock::at(0x017f_22e2_79b0));
+
+        let a = GEN.next_uuid();
+        let b = GEN.next_uuid();
+        assert_eq!(a.version(), 7);
+        assert_eq!(a.to_u128() >> 62 & 0b11, 0b10, "RFC 9562 variant");
+        assert_eq!(a.timestamp(), 0x017f_22e2_79b0);
+        assert!(b > a);
+        assert!(a.to_string().starts_with("017f22e2-79b0-7"));
+
+        assert_eq!(Uuid::now_v7().version(), 7);
+    }
+
+    #[test]
+    fn test_v8_counter() {
+        static COUNTER: UidGenerator = UidGenerator::new();
+        let gen = UuidV8Generator::with_clock(FakeClock::at(1234)).with_counter(&COUNTER);
+
+        let uuid = gen.next_uuid();
+        assert_eq!(uuid.version(), 8);
+        assert_eq!(uuid.timestamp(), 1234);
+        assert_eq!(uuid.to_u128() >> 62 & 0b11, 0b10);
+        assert_eq!(uuid.to_u128() & ((1 << 62) - 1), 512, "the first ID from a fresh generator");
+
+        let custom = Uuid::new_v8(0xffff_ffff_ffff, 0xfff, u64::MAX);
+        assert_eq!(custom.to_string(), "ffffffff-ffff-8fff-bfff-ffffffffffff");
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid_crate_conversions() {
+        let uuid = Uuid::now_v7();
+        let other = ::uuid::Uuid::from(uuid);
+        assert_eq!(other.get_version_num(), 7);
+        assert_eq!(other.to_string(), uuid.to_string());
+        assert_eq!(Uuid::from(other), uuid);
+    }
+}